@@ -2,14 +2,12 @@ extern crate io;
 extern crate referee;
 extern crate solutions;
 
-use io::traits::{ReadInput, WriteOutput};
-use referee::{InitInput, TurnInput};
-use solutions::create_solution;
-use std::{env::args, error::Error, ops::ControlFlow, time::Duration};
+use io::source::Source;
+use std::{env::args, error::Error, io::stdout};
 
-pub type Result<T, E = Box<dyn Error>> = std::result::Result<T, E>;
+pub use referee::TIME_LIMIT;
 
-pub const TIME_LIMIT: Duration = Duration::from_millis(5800);
+pub type Result<T, E = Box<dyn Error>> = std::result::Result<T, E>;
 
 fn main() {
     let name = if let Some(solution_name) = args().nth(1) {
@@ -25,18 +23,5 @@ fn main() {
 }
 
 fn run(name: &str) {
-    let input = InitInput::read();
-    let mut brain =
-        create_solution(name, input).unwrap_or_else(|| panic!("unknown solution: {}", name));
-
-    loop {
-        let input = TurnInput::read();
-        match brain.think(input) {
-            ControlFlow::Continue(output) => output.write(),
-            ControlFlow::Break(output) => {
-                output.write();
-                break;
-            }
-        }
-    }
+    solutions::run(name, &mut Source::new_stdin(), &mut stdout().lock());
 }
@@ -55,12 +55,9 @@ macro_rules! input {
         }
     };
     ($($rest:tt)*) => {
-        // This `io` is...
-        // - A `io` submodule of `io` crate in local development; crate::io::io.
-        // - Bundled `io` module (= original `io` crate root) after bundler.
         #[allow(unused_mut)]
-        let mut locked_stdin = $crate::io::STDIN_SOURCE
-            .get_or_init(|| std::sync::Mutex::new($crate::io::source::Source::new_stdin()))
+        let mut locked_stdin = $crate::STDIN_SOURCE
+            .get_or_init(|| std::sync::Mutex::new($crate::source::Source::new_stdin()))
             .lock()
             .unwrap();
         $crate::input! {
@@ -78,7 +75,7 @@ macro_rules! read_value {
         $crate::read_value!(@array @source [$source] @kind [] @rest $($kind)*)
     };
     (@array @source [$source:expr] @kind [$($kind:tt)*] @rest) => {{
-        let len = <usize as $crate::io::source::Readable>::read($source);
+        let len = <usize as $crate::source::Readable>::read($source);
         $crate::read_value!(@source [$source] @kind [[$($kind)*; len]])
     }};
     (@array @source [$source:expr] @kind [$($kind:tt)*] @rest ; $($rest:tt)*) => {
@@ -120,17 +117,14 @@ macro_rules! read_value {
 
     // normal other
     (@source [$source:expr] @kind [$kind:ty]) => {
-        <$kind as $crate::io::source::Readable>::read($source)
+        <$kind as $crate::source::Readable>::read($source)
     };
 
     // human-friendly version
     ($($kind:tt)*) => {{
-        // This `io` is...
-        // - A `io` submodule of `io` crate in local development; crate::io::io.
-        // - Bundled `io` module (= original `io` crate root) after bundler.
         #[allow(unused_mut)]
-        let mut locked_stdin = $crate::io::STDIN_SOURCE
-            .get_or_init(|| std::sync::Mutex::new($crate::io::source::Source::new_stdin()))
+        let mut locked_stdin = $crate::STDIN_SOURCE
+            .get_or_init(|| std::sync::Mutex::new($crate::source::Source::new_stdin()))
             .lock()
             .unwrap();
         $crate::read_value! {
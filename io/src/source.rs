@@ -11,6 +11,10 @@ pub struct Source<R = BufReader<Stdin>> {
     tokens: Peekable<SplitWhitespace<'static>>,
     context: Option<NonNull<str>>,
     reader: R,
+    /// Number of lines read so far
+    line: usize,
+    /// Number of tokens read from the current line
+    token: usize,
 }
 
 impl<R> Drop for Source<R> {
@@ -40,6 +44,8 @@ impl<R: BufRead> Source<R> {
             tokens: "".split_whitespace().peekable(),
             context: None,
             reader,
+            line: 0,
+            token: 0,
         }
     }
 
@@ -61,6 +67,8 @@ impl<R: BufRead> Source<R> {
                 return;
             }
 
+            self.line += 1;
+            self.token = 0;
             let context = NonNull::new(Box::leak(line.into_boxed_str())).unwrap();
 
             // SAFETY: we drop context only after dropping previous tokens (hence no reference to
@@ -83,7 +91,18 @@ impl<R: BufRead> Source<R> {
 
     pub fn next_token(&mut self) -> Option<&str> {
         self.prepare();
-        self.tokens.next()
+        let token = self.tokens.next()?;
+        self.token += 1;
+
+        Some(token)
+    }
+
+    /// 1-based line and token index of the next token. At EOF, the position just after the last
+    /// token.
+    pub fn position(&mut self) -> (usize, usize) {
+        self.prepare();
+
+        (self.line.max(1), self.token + 1)
     }
 
     pub fn is_empty(&mut self) -> bool {
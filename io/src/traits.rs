@@ -23,6 +23,25 @@ pub trait ReadInput {
     }
 }
 
+/// Writes what `ReadInput` reads, e.g. to feed a solution from a local interactive judge.
+pub trait WriteInput {
+    fn write_to<W: Write>(&self, b: &mut W);
+}
+
+/// Reads back what `WriteOutput` wrote, e.g. to validate or score outputs locally.
+pub trait ReadOutput {
+    fn read_from<R: BufRead>(source: &mut Source<R>) -> Self
+    where
+        Self: Sized;
+
+    fn read_from_str(s: &str) -> Self
+    where
+        Self: Sized,
+    {
+        Self::read_from(&mut Source::new(s.as_bytes()))
+    }
+}
+
 pub trait WriteOutput {
     fn write_to<W: Write>(&self, b: &mut W);
 
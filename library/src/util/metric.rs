@@ -0,0 +1,89 @@
+use std::{cell::RefCell, fmt::Display};
+
+/// 計測値を表す stderr の行の接頭辞
+pub const PREFIX: &str = "@@metric";
+
+thread_local! {
+    /// `capture` 中に報告された計測値の行
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// ローカルのテストランナーにケースごとの計測値 (焼きなましの反復回数など) を報告する。
+///
+/// `metric!("iterations", n)` のように使う。キーは空白を含まないこと。同じキーを複数回報告した場合は
+/// 最後の値が使われる。`local` feature が無効な場合 (= 提出時) は何も出力しない。
+#[macro_export]
+macro_rules! metric {
+    ($key:expr, $value:expr $(,)?) => {{
+        // マクロ内の `cfg` は呼び出し側のクレートの feature で判定されるので、切り替えは `emit` で行う
+        $crate::util::metric::emit($key, &$value)
+    }};
+}
+
+/// 計測値を 1 行として stderr に書き出す。`capture` 中はそちらに記録する。
+#[cfg(feature = "local")]
+pub fn emit(key: &str, value: &dyn Display) {
+    let line = format!("{PREFIX} {key} {value}");
+    let captured = CAPTURED.with(|captured| {
+        captured
+            .borrow_mut()
+            .as_mut()
+            .map(|lines| lines.push(line.clone()))
+            .is_some()
+    });
+    if !captured {
+        eprintln!("{line}");
+    }
+}
+
+/// `local` feature が無効な場合は何もしない。
+#[cfg(not(feature = "local"))]
+#[inline]
+pub fn emit(_key: &str, _value: &dyn Display) {}
+
+/// `f` の実行中にこのスレッドで報告された計測値の行を stderr に書き出さずに集める。
+pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(vec![]));
+    let result = f();
+    let lines = CAPTURED
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default();
+
+    (result, lines)
+}
+
+/// 計測値の行であればキーと値に分解する。
+pub fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(PREFIX)?.strip_prefix(' ')?;
+    let (key, value) = rest.split_once(' ')?;
+
+    Some((key, value.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "local")]
+    #[test]
+    fn test_capture() {
+        let ((), lines) = capture(|| {
+            emit("iterations", &42);
+            crate::metric!("best", 1.5)
+        });
+
+        assert_eq!(lines, vec!["@@metric iterations 42", "@@metric best 1.5"]);
+        assert_eq!(parse_line(&lines[0]), Some(("iterations", "42")));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("@@metric beam width 3\r"),
+            Some(("beam", "width 3"))
+        );
+        assert_eq!(parse_line("@@metricx a 1"), None);
+        assert_eq!(parse_line("@@metric a"), None);
+        assert_eq!(parse_line("score = 1"), None);
+    }
+}
@@ -1 +1,2 @@
+pub mod metric;
 pub mod per;
@@ -1,5 +1,10 @@
 use itertools::{izip, Itertools};
-use std::fmt;
+use std::{error, fmt, str::FromStr, time::Duration};
+use validation::{ParsedOutput, Position, ReadTokens, ValidationError};
+
+pub mod validation;
+
+pub const TIME_LIMIT: Duration = Duration::from_millis(5800);
 
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "local", derive(serde::Serialize, serde::Deserialize))]
@@ -29,20 +34,142 @@ pub struct TurnInput {}
 
 impl TurnInput {}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Output {
     pub operations: Vec<Operation>,
 }
 
-impl Output {}
+impl Output {
+    /// Parses the format written by `WriteOutput`: the number of operations followed by the
+    /// operations. Returns the position of each operation as well.
+    pub fn parse_from(
+        tokens: &mut impl ReadTokens,
+    ) -> Result<(Output, Vec<Position>), ValidationError> {
+        let count: usize = tokens.parse("number of operations")?;
+
+        let mut operations = vec![];
+        let mut positions = vec![];
+        for _ in 0..count {
+            positions.push(tokens.position());
+            operations.push(Operation::parse_from(tokens)?);
+        }
+
+        Ok((Output { operations }, positions))
+    }
+}
+
+impl FromStr for Output {
+    type Err = ScoreError;
+
+    fn from_str(s: &str) -> Result<Self, ScoreError> {
+        Ok(ParsedOutput::parse(s)?.output)
+    }
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operation {}
 
-impl Operation {}
+impl Operation {
+    pub fn parse_from(tokens: &mut impl ReadTokens) -> Result<Operation, ValidationError> {
+        let (position, token) = tokens.next_token("operation")?;
+        Err(ValidationError::at(
+            position,
+            format!("unknown operation: `{token}`"),
+        ))
+    }
+}
 
 impl fmt::Display for Operation {
     fn fmt(&self, _b: &mut fmt::Formatter) -> fmt::Result {
         match *self {}
     }
 }
+
+/// Native scorer, used by the local test runner instead of the official visualizer.
+///
+/// Implement `compute_score` to enable it. Until then, and for outputs which cannot be parsed into
+/// an `Output`, the score is read from the visualizer output.
+pub trait ComputeScore {
+    /// Score of `output`, or `None` if the native scorer is not implemented.
+    fn compute_score(&self, _output: &Output) -> Option<Result<i64, ScoreError>> {
+        None
+    }
+}
+
+impl ComputeScore for InitInput {}
+
+/// Local judge of an interactive problem, used by the local test runner when no official tester is
+/// available.
+///
+/// The runner sends the `TurnInput`s to the solution one by one and passes back its `Output` for
+/// each turn, until `next_turn` returns `None`.
+pub trait InteractiveJudge {
+    /// Input of the next turn, or `None` if the interaction is over.
+    fn next_turn(&mut self) -> Option<TurnInput>;
+
+    /// Receives the output of the solution for the last turn.
+    fn receive(&mut self, output: &Output) -> Result<(), ScoreError>;
+
+    /// Final score, called once the interaction is over.
+    fn score(&self) -> Result<i64, ScoreError>;
+}
+
+/// Creates the `InteractiveJudge` for an input.
+///
+/// Implement `create_judge` to enable it. The solution receives the `InitInput` as written by
+/// `io::traits::WriteInput`, while the judge sees all of it.
+pub trait CreateJudge {
+    /// `None` if the problem is not interactive.
+    fn create_judge(&self) -> Option<Box<dyn InteractiveJudge>> {
+        None
+    }
+}
+
+impl CreateJudge for InitInput {}
+
+/// Reason why an output could not be scored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScoreError {
+    /// The output is malformed.
+    Parse(String),
+    /// The output is well-formed but violates the rules of the problem.
+    Invalid(String),
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, b: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreError::Parse(message) => write!(b, "parse error: {message}"),
+            ScoreError::Invalid(message) => write!(b, "invalid output: {message}"),
+        }
+    }
+}
+
+impl error::Error for ScoreError {}
+
+impl From<ValidationError> for ScoreError {
+    fn from(e: ValidationError) -> Self {
+        ScoreError::Parse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        assert_eq!(
+            "0\n".parse::<Output>().unwrap().operations.len(),
+            0,
+            "empty output"
+        );
+        assert!(matches!("".parse::<Output>(), Err(ScoreError::Parse(_))));
+        assert!(matches!("x\n".parse::<Output>(), Err(ScoreError::Parse(_))));
+        assert!(matches!("1\n".parse::<Output>(), Err(ScoreError::Parse(_))));
+        assert!(matches!(
+            "0\n0\n".parse::<Output>(),
+            Err(ScoreError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,319 @@
+use std::{error, fmt, str::FromStr};
+
+use crate::{InitInput, Output, TurnInput};
+
+/// 1-based position of a token in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub token: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, b: &mut fmt::Formatter) -> fmt::Result {
+        write!(b, "line {}, token {}", self.line, self.token)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// `None` if the error concerns the output as a whole.
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn at(position: Position, message: impl Into<String>) -> Self {
+        ValidationError {
+            position: Some(position),
+            message: message.into(),
+        }
+    }
+
+    pub fn whole(message: impl Into<String>) -> Self {
+        ValidationError {
+            position: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, b: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(b, "{}: {}", position, self.message),
+            None => write!(b, "{}", self.message),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// Whitespace-separated tokens of an output, with their positions.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    tokens: Vec<(Position, &'a str)>,
+    next: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(s: &'a str) -> Self {
+        let tokens = s
+            .lines()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                line.split_whitespace()
+                    .enumerate()
+                    .map(move |(token_index, token)| {
+                        let position = Position {
+                            line: line_index + 1,
+                            token: token_index + 1,
+                        };
+                        (position, token)
+                    })
+            })
+            .collect();
+
+        Tokens { tokens, next: 0 }
+    }
+
+    /// Position just after the last token. Errors at this position mean that the output ended
+    /// too early.
+    pub fn end_position(&self) -> Position {
+        match self.tokens.last() {
+            Some(&(last, _)) => Position {
+                line: last.line,
+                token: last.token + 1,
+            },
+            None => Position { line: 1, token: 1 },
+        }
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.next == self.tokens.len()
+    }
+
+    pub fn expect_end(&self) -> Result<(), ValidationError> {
+        match self.tokens.get(self.next) {
+            Some(&(position, token)) => Err(ValidationError::at(
+                position,
+                format!("expected EOF, found `{token}`"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ReadTokens for Tokens<'_> {
+    fn position(&mut self) -> Position {
+        match self.tokens.get(self.next) {
+            Some(&(position, _)) => position,
+            None => self.end_position(),
+        }
+    }
+
+    fn next_token(&mut self, what: &str) -> Result<(Position, String), ValidationError> {
+        let position = self.position();
+        let &(_, token) = self
+            .tokens
+            .get(self.next)
+            .ok_or_else(|| ValidationError::at(position, format!("expected {what}, found EOF")))?;
+        self.next += 1;
+
+        Ok((position, token.to_string()))
+    }
+}
+
+/// Reads the tokens of an output one by one, for `Output::parse_from`. Implemented by `Tokens`
+/// here and by `io::source::Source` so that outputs are parsed in the same way everywhere.
+pub trait ReadTokens {
+    /// Position of the next token. At the end of the output, the position just after the last
+    /// token.
+    fn position(&mut self) -> Position;
+
+    /// Reads the next token. `what` describes the expected token in the error message.
+    fn next_token(&mut self, what: &str) -> Result<(Position, String), ValidationError>;
+
+    /// Reads and parses the next token.
+    fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, ValidationError>
+    where
+        Self: Sized,
+    {
+        let (position, token) = self.next_token(what)?;
+        token
+            .parse()
+            .map_err(|_| ValidationError::at(position, format!("expected {what}, found `{token}`")))
+    }
+}
+
+/// Output parsed together with the position of each operation.
+#[derive(Debug, Clone)]
+pub struct ParsedOutput {
+    pub output: Output,
+    pub operation_positions: Vec<Position>,
+}
+
+impl ParsedOutput {
+    pub fn parse(s: &str) -> Result<Self, ValidationError> {
+        let mut tokens = Tokens::new(s);
+        let (output, operation_positions) = Output::parse_from(&mut tokens)?;
+        tokens.expect_end()?;
+
+        Ok(ParsedOutput {
+            output,
+            operation_positions,
+        })
+    }
+}
+
+/// Checks an output against the rules of the problem, used by the local test runner.
+///
+/// Implement `validate` to enable it. Until then outputs are only checked to be parsed into an
+/// `Output`, and the rules are left to the scorer.
+pub trait Validate {
+    /// Validates the output of a turn against the input of the turn, or `None` if the validator is
+    /// not implemented. Constraints which depend on earlier turns belong to the `InteractiveJudge`.
+    fn validate(
+        &self,
+        _turn: &TurnInput,
+        _output: &ParsedOutput,
+    ) -> Option<Result<(), ValidationError>> {
+        None
+    }
+}
+
+impl Validate for InitInput {}
+
+/// Parses the outputs of consecutive turns from `s` and validates each against `input` and the
+/// input of its turn, returning the first error. `turns` is consumed only as far as there are
+/// outputs, so it may read the turn inputs lazily. Outputs left after the last turn are an error.
+pub fn validate_output(
+    input: &impl Validate,
+    turns: impl IntoIterator<Item = TurnInput>,
+    s: &str,
+) -> Result<Vec<Output>, ValidationError> {
+    let mut tokens = Tokens::new(s);
+    let mut outputs = vec![];
+    for turn in turns {
+        let (output, operation_positions) = Output::parse_from(&mut tokens)?;
+        let parsed = ParsedOutput {
+            output,
+            operation_positions,
+        };
+        if let Some(result) = input.validate(&turn, &parsed) {
+            result?;
+        }
+        outputs.push(parsed.output);
+
+        if tokens.is_at_end() {
+            break;
+        }
+    }
+    // Outputs after the last turn
+    tokens.expect_end()?;
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut tokens = Tokens::new("3 x\n\n  7\n");
+
+        assert_eq!(tokens.parse::<u32>("count").unwrap(), 3);
+        assert_eq!(
+            tokens.parse::<u32>("number").unwrap_err(),
+            ValidationError::at(Position { line: 1, token: 2 }, "expected number, found `x`")
+        );
+        assert_eq!(tokens.position(), Position { line: 3, token: 1 });
+        assert_eq!(tokens.end_position(), Position { line: 3, token: 2 });
+        assert_eq!(tokens.next_token("value").unwrap().1, "7");
+        assert!(tokens.expect_end().is_ok());
+        assert_eq!(
+            tokens.next_token("value").unwrap_err().to_string(),
+            "line 3, token 2: expected value, found EOF"
+        );
+    }
+
+    #[test]
+    fn test_parse_output() {
+        assert!(ParsedOutput::parse("0\n")
+            .unwrap()
+            .output
+            .operations
+            .is_empty());
+        assert_eq!(
+            ParsedOutput::parse("0\n1").unwrap_err().position,
+            Some(Position { line: 2, token: 1 })
+        );
+        assert_eq!(
+            ParsedOutput::parse("").unwrap_err().to_string(),
+            "line 1, token 1: expected number of operations, found EOF"
+        );
+    }
+
+    #[test]
+    fn test_validate_turns() {
+        let input = InitInput {};
+        let turns = || std::iter::repeat(TurnInput {});
+
+        assert_eq!(
+            validate_output(&input, turns(), "0\n0\n0\n").unwrap().len(),
+            3
+        );
+        assert_eq!(
+            validate_output(&input, turns().take(2), "0\n0\n0\n").unwrap_err(),
+            ValidationError::at(Position { line: 3, token: 1 }, "expected EOF, found `0`")
+        );
+        assert_eq!(
+            validate_output(&input, turns(), "0\nx\n")
+                .unwrap_err()
+                .position,
+            Some(Position { line: 2, token: 1 })
+        );
+        assert!(validate_output(&input, turns(), "").is_err());
+    }
+
+    #[test]
+    fn test_validate_turns_by_rules() {
+        /// Rejects every output.
+        struct RejectAll;
+
+        impl Validate for RejectAll {
+            fn validate(
+                &self,
+                _turn: &TurnInput,
+                _output: &ParsedOutput,
+            ) -> Option<Result<(), ValidationError>> {
+                Some(Err(ValidationError::whole("rejected")))
+            }
+        }
+
+        let turns = || std::iter::repeat(TurnInput {});
+        assert_eq!(
+            validate_output(
+                &RejectAll,
+                turns(),
+                "0
+"
+            )
+            .unwrap_err(),
+            ValidationError::whole("rejected")
+        );
+        // Outputs are parsed before the rules are checked.
+        assert_eq!(
+            validate_output(
+                &RejectAll,
+                turns(),
+                "x
+"
+            )
+            .unwrap_err()
+            .position,
+            Some(Position { line: 1, token: 1 })
+        );
+    }
+}
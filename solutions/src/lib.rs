@@ -2,8 +2,15 @@ extern crate io;
 extern crate library;
 extern crate referee;
 
-use std::ops::ControlFlow;
+use std::{
+    io::{BufRead, Write},
+    ops::ControlFlow,
+};
 
+use io::{
+    source::Source,
+    traits::{ReadInput, WriteOutput},
+};
 use referee::{InitInput, Output, TurnInput};
 
 pub mod naive;
@@ -45,3 +52,27 @@ macro_rules! define_solutions {
 }
 
 define_solutions![NaiveSolution];
+
+/// Runs the solution `name` to the end, reading the inputs from `source` and writing the outputs to
+/// `out`. Panics if there is no such solution.
+pub fn run<R: BufRead, W: Write>(name: &str, source: &mut Source<R>, out: &mut W) {
+    let input = InitInput::read_from(source);
+    let mut brain =
+        create_solution(name, input).unwrap_or_else(|| panic!("unknown solution: {}", name));
+
+    loop {
+        let input = TurnInput::read_from(source);
+        let (output, finished) = match brain.think(input) {
+            ControlFlow::Continue(output) => (output, false),
+            ControlFlow::Break(output) => (output, true),
+        };
+
+        // Flush for interactive problems, since the next input depends on this output.
+        output.write_to(out);
+        out.flush().unwrap();
+
+        if finished {
+            break;
+        }
+    }
+}
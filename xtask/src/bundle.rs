@@ -1,6 +1,5 @@
 use miette::{miette, Context, IntoDiagnostic, Result};
 use quote::ToTokens;
-use regex::{Captures, Regex};
 use std::{
     collections::{HashMap, VecDeque},
     fs::{self, read_to_string},
@@ -19,19 +18,83 @@ use syn::{
 };
 use toml::{Table, Value};
 
+use self::source_map::{SourceMap, MAP_PATH};
+use crate::sources::find_solution_modules;
+
+mod macros;
+mod minify;
+mod prune;
+mod select;
+pub mod source_map;
+
 // 100 KB 以上のソースコードは minify する
 const MINIFY_THRESHOLD: usize = 100 * 1000;
 
+// ジャッジの Rust のエディション。`cargo new` は最新のエディションを使うが、2024 では `gen` などが予約語
+// になっていて、`library` のように識別子として使っているとコンパイルできない。マクロの扱いとは関係なく
+// 必要になる
+const JUDGE_EDITION: &str = "2021";
+
 pub fn main(args: &[String]) -> Result<()> {
-    let skip_check = !args.is_empty() && args[0] == "--skip-check";
+    let mut skip_check = false;
+    let mut no_prune = false;
+    let mut annotate = false;
+    let mut solution = None;
+    let mut file_path: PathBuf = vec!["driver", "src", "main.rs"].into_iter().collect();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--skip-check" => skip_check = true,
+            // 到達可能性の判定が誤っているときの逃げ道
+            "--no-prune" => no_prune = true,
+            "--annotate" => annotate = true,
+            "--solution" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| miette!("--solution requires a solution name"))?;
+                solution = Some(name.clone());
+            }
+            // driver 以外のバイナリをバンドルする
+            "--entry" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| miette!("--entry requires a path"))?;
+                file_path = PathBuf::from(path);
+            }
+            _ => return Err(miette!("unknown option: {arg}")),
+        }
+    }
 
-    let file_path: PathBuf = vec!["driver", "src", "main.rs"].into_iter().collect();
-    let parsed = expand(&file_path)?;
+    let mut parsed = expand(&file_path, annotate)?;
+    if let Some(solution) = &solution {
+        let modules = find_solution_modules()?;
+        select::select_solution(&mut parsed, &modules, solution)
+            .wrap_err_with(|| format!("failed to select solution `{solution}`"))?;
+    }
+    if !no_prune {
+        let pruned = prune::prune(&mut parsed);
+        eprintln!("pruned {pruned} unreachable items");
+    }
+    // 枝刈りではルートのアイテムを常に残すので、枝刈りの後に移動する
+    macros::hoist_exported_macros(&mut parsed);
 
     let stream = parsed.to_token_stream();
     let mut formatted = format(&stream.to_string())?;
-    if formatted.len() > MINIFY_THRESHOLD {
-        formatted = minify(&formatted)?;
+    if annotate {
+        // 行の対応が意味をなさなくなるので minify はしない
+        if formatted.len() > MINIFY_THRESHOLD {
+            eprintln!(
+                "warning: the bundle is {} bytes, over {MINIFY_THRESHOLD} bytes, but is not \
+                 minified with --annotate; it may be rejected by the judge",
+                formatted.len()
+            );
+        }
+        let (annotated, source_map) = SourceMap::resolve_markers(&formatted);
+        source_map.write(Path::new(MAP_PATH))?;
+        eprintln!("wrote source map to {MAP_PATH}");
+        formatted = annotated;
+    } else if formatted.len() > MINIFY_THRESHOLD {
+        formatted = minify::minify(&parsed);
     }
 
     if !skip_check {
@@ -213,10 +276,15 @@ fn fix_path(
     Ok(())
 }
 
-pub fn expand_mod_all(crate_ident: Option<&str>, file: &mut File, file_path: &Path) -> Result<()> {
+pub fn expand_mod_all(
+    crate_ident: Option<&str>,
+    file: &mut File,
+    file_path: &Path,
+    annotate: bool,
+) -> Result<()> {
     for item in &mut file.items {
         if let Item::Mod(module) = item {
-            expand_mod(crate_ident, module, file_path)?;
+            expand_mod(crate_ident, module, file_path, annotate)?;
         }
     }
 
@@ -227,6 +295,7 @@ pub fn expand_mod(
     crate_ident: Option<&str>,
     module: &mut ItemMod,
     container_path: &Path,
+    annotate: bool,
 ) -> Result<()> {
     if module.content.is_some() {
         return Ok(());
@@ -234,7 +303,10 @@ pub fn expand_mod(
 
     let file_path = find_mod_file(module, container_path)
         .ok_or_else(|| miette!("failed to find module source: {}", module.ident))?;
-    let parsed = expand_file_under_crate(crate_ident, &file_path)?;
+    let parsed = expand_file_under_crate(crate_ident, &file_path, annotate)?;
+    if annotate {
+        source_map::mark_module(&mut module.attrs, &file_path);
+    }
 
     module.content = Some((Brace(module.semi.span()), parsed.items));
     module.semi = None;
@@ -277,7 +349,11 @@ fn extract_extern_crates(parsed: &mut File) -> Vec<ItemExternCrate> {
     extern_crates
 }
 
-fn expand_file_under_crate(crate_ident: Option<&str>, file_path: &Path) -> Result<File> {
+fn expand_file_under_crate(
+    crate_ident: Option<&str>,
+    file_path: &Path,
+    annotate: bool,
+) -> Result<File> {
     let source = read_to_string(file_path)
         .into_diagnostic()
         .wrap_err_with(|| {
@@ -294,7 +370,10 @@ fn expand_file_under_crate(crate_ident: Option<&str>, file_path: &Path) -> Resul
             crate_ident
         )
     })?;
-    expand_mod_all(crate_ident, &mut parsed, file_path)
+    if annotate {
+        source_map::mark_items(&mut parsed, file_path);
+    }
+    expand_mod_all(crate_ident, &mut parsed, file_path, annotate)
         .wrap_err_with(|| format!("failed to expand crate `{:?}`", crate_ident))?;
 
     Ok(parsed)
@@ -303,6 +382,7 @@ fn expand_file_under_crate(crate_ident: Option<&str>, file_path: &Path) -> Resul
 fn expand_crate(
     crate_ident: Option<&str>,
     file_path: &Path,
+    annotate: bool,
 ) -> Result<(File, Vec<ItemExternCrate>)> {
     let source = read_to_string(file_path)
         .into_diagnostic()
@@ -311,7 +391,10 @@ fn expand_crate(
         .into_diagnostic()
         .wrap_err("failed to parse source")?;
     let extern_crates = extract_extern_crates(&mut parsed);
-    expand_mod_all(crate_ident, &mut parsed, file_path)
+    if annotate {
+        source_map::mark_items(&mut parsed, file_path);
+    }
+    expand_mod_all(crate_ident, &mut parsed, file_path, annotate)
         .wrap_err_with(|| format!("failed to expand all modules for crate `{crate_ident:?}`"))?;
 
     let external_crate_idents = extern_crates
@@ -326,8 +409,13 @@ fn expand_crate(
     Ok((parsed, extern_crates))
 }
 
-pub fn expand(file_path: &Path) -> Result<File> {
-    let (mut main_crate, extern_crates) = expand_crate(None, file_path)
+pub fn expand(file_path: &Path, annotate: bool) -> Result<File> {
+    expand_in(Path::new(""), file_path, annotate)
+}
+
+/// `workspace_root` にあるクレートを展開する。
+fn expand_in(workspace_root: &Path, file_path: &Path, annotate: bool) -> Result<File> {
+    let (mut main_crate, extern_crates) = expand_crate(None, file_path, annotate)
         .wrap_err_with(|| format!("failed to expand crates at `{}`", file_path.display()))?;
 
     let mut expanded_crates = HashMap::new();
@@ -338,25 +426,31 @@ pub fn expand(file_path: &Path) -> Result<File> {
             continue;
         }
 
-        let path: PathBuf = vec![&*crate_ident.replace('_', "-"), "src", "lib.rs"]
-            .into_iter()
-            .collect();
-        let (expanded, another_extern_crates) = expand_crate(Some(&crate_ident), &path)
+        let path = workspace_root
+            .join(crate_ident.replace('_', "-"))
+            .join("src")
+            .join("lib.rs");
+        let (expanded, another_extern_crates) = expand_crate(Some(&crate_ident), &path, annotate)
             .wrap_err_with(|| {
-                format!(
-                    "failed to expand crate `{crate_ident:?}` at `{}`",
-                    path.display()
-                )
-            })?;
+            format!(
+                "failed to expand crate `{crate_ident:?}` at `{}`",
+                path.display()
+            )
+        })?;
         queue.extend(another_extern_crates);
-        expanded_crates.insert(crate_ident, expanded);
+        expanded_crates.insert(crate_ident, (path, expanded));
     }
 
+    let crate_idents = expanded_crates.keys().cloned().collect::<Vec<_>>();
     let original_items = take(&mut main_crate.items);
-    for (crate_ident, krate) in expanded_crates {
+    for (crate_ident, (path, krate)) in expanded_crates {
         let span = krate.span();
+        let mut attrs = vec![];
+        if annotate {
+            source_map::mark_module(&mut attrs, &path);
+        }
         let item = Item::Mod(ItemMod {
-            attrs: vec![],
+            attrs,
             vis: Visibility::Inherited,
             mod_token: syn::token::Mod { span },
             ident: Ident::new(&crate_ident, span),
@@ -367,6 +461,7 @@ pub fn expand(file_path: &Path) -> Result<File> {
         main_crate.items.push(item);
     }
     main_crate.items.extend(original_items);
+    macros::fix_macro_paths(&mut main_crate, &crate_idents);
 
     Ok(main_crate)
 }
@@ -393,71 +488,12 @@ pub fn format(source: &str) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-pub fn minify(source: &str) -> Result<String> {
-    // パースして構文的な正しさを確認し、文字列リテラルを特定
-    let _ast: File = parse_str(source)
-        .into_diagnostic()
-        .wrap_err("failed to parse source for minification")?;
-
-    // 文字列リテラルをプレースホルダーに置き換えて保持
-    let mut literals = HashMap::new();
-    let mut modified = source.to_string();
-    let re_string = Regex::new(r#""(?:[^"\\]|\\.)*""#).unwrap();
-
-    modified = re_string
-        .replace_all(&modified, |caps: &Captures| {
-            loop {
-                // プレースホルダーは1つの乱数値を使用
-                let placeholder = format!("@@__LITERAL_{:016x}@@", rand::random::<u64>());
-                // 衝突しなければこのプレースホルダーを使用
-                if literals
-                    .insert(placeholder.clone(), caps[0].to_string())
-                    .is_none()
-                {
-                    return placeholder;
-                }
-            }
-        })
-        .to_string();
-
-    // 1. コメントを除去 (// と /**/ 形式両方)
-    let re_line_comments = Regex::new(r"//[^\n]*").unwrap();
-    let re_block_comments = Regex::new(r"/\*([^*]|\*[^/])*\*/").unwrap();
-    let mut minified = re_line_comments.replace_all(&modified, "").to_string();
-    minified = re_block_comments.replace_all(&minified, "").to_string();
-
-    // 2. 連続する空白を1つに
-    let re_spaces = Regex::new(r"\s+").unwrap();
-    minified = re_spaces.replace_all(&minified, " ").to_string();
-
-    // 3. 特定のパターンの空白を除去（両端または片端の空白に対応）
-    // 変化がなくなるまで繰り返し適用
-    let re_operators = Regex::new(
-        r"\s*([:;,=(){}\[\]<>@\$\.\|\+\-\*/&#!%^~]|\->|::|=>|\.\.|\+=|-=|\*=|/=|&=|\|=|%=|\^=)\s*",
-    )
-    .unwrap();
-    loop {
-        let new_minified = re_operators
-            .replace_all(&minified, |caps: &Captures| caps[1].to_string())
-            .to_string();
-        if new_minified == minified {
-            break;
-        }
-        minified = new_minified;
-    }
-
-    // 4. 行頭と行末の空白を除去
-    minified = minified.trim().to_string();
-
-    // 5. プレースホルダーを元の文字列に戻す
-    for (placeholder, literal) in literals {
-        minified = minified.replace(&placeholder, &literal);
-    }
-
-    Ok(minified)
+pub fn check_compile(source: &str) -> Result<()> {
+    check_compile_in(Path::new(""), source)
 }
 
-pub fn check_compile(source: &str) -> Result<()> {
+/// `workspace_root` の依存関係でコンパイルできるか確認する。
+fn check_compile_in(workspace_root: &Path, source: &str) -> Result<()> {
     let temp_dir = tempfile::tempdir()
         .into_diagnostic()
         .wrap_err("failed to create temporary directory")?;
@@ -466,6 +502,7 @@ pub fn check_compile(source: &str) -> Result<()> {
     let status = Command::new("cargo")
         .arg("new")
         .arg("--bin")
+        .args(["--edition", JUDGE_EDITION])
         .arg("submission")
         .current_dir(temp_dir.path())
         .spawn()
@@ -487,7 +524,7 @@ pub fn check_compile(source: &str) -> Result<()> {
         .parse::<Table>()
         .into_diagnostic()
         .wrap_err("failed to parse temporary diagnostic")?;
-    let workspace_manifest = fs::read_to_string("Cargo.toml")
+    let workspace_manifest = fs::read_to_string(workspace_root.join("Cargo.toml"))
         .into_diagnostic()
         .wrap_err("failed to read original manifest")?
         .parse::<Table>()
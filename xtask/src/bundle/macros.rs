@@ -0,0 +1,297 @@
+use proc_macro2::{Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use std::collections::{HashMap, HashSet};
+use syn::{
+    punctuated::Punctuated,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    File, Item, ItemMacro, ItemUse, UseGroup, UseTree,
+};
+
+// `#[macro_export]` されたマクロは、バンドル後はそのクレートのモジュールではなくバンドル結果のルートに
+// 置かれる。一方でマクロの定義中の `$crate` はバンドル結果のルートを指すようになるので、クレート内の
+// アイテムへのパスにはクレートのモジュールを補う必要がある。
+
+/// バンドルしたクレート `crate_idents` のマクロが、バンドル後も元と同じアイテムを指すようにする。
+///
+/// - マクロの定義中の `$crate::path` を `$crate::<crate>::path` に書き換える。ただし `#[macro_export]`
+///   されたマクロはルートにあるので書き換えない。
+/// - `crate::<crate>::macro_name` に書き換えられた `#[macro_export]` されたマクロへのパスを
+///   `crate::macro_name` に戻す。
+pub fn fix_macro_paths(file: &mut File, crate_idents: &[String]) {
+    let exported = exported_macros(file, crate_idents);
+
+    for item in &mut file.items {
+        let Item::Mod(module) = item else {
+            continue;
+        };
+        let crate_ident = module.ident.to_string();
+        let Some(macros) = exported.get(&crate_ident) else {
+            continue;
+        };
+
+        struct DollarCrateFixer<'a> {
+            crate_ident: &'a Ident,
+            macros: &'a HashSet<String>,
+        }
+
+        impl VisitMut for DollarCrateFixer<'_> {
+            fn visit_item_macro_mut(&mut self, i: &mut ItemMacro) {
+                if i.ident.is_some() {
+                    i.mac.tokens =
+                        prefix_dollar_crate(i.mac.tokens.clone(), self.crate_ident, self.macros);
+                }
+                visit_mut::visit_item_macro_mut(self, i);
+            }
+        }
+
+        let crate_ident = module.ident.clone();
+        DollarCrateFixer {
+            crate_ident: &crate_ident,
+            macros,
+        }
+        .visit_item_mod_mut(module);
+    }
+
+    struct PathFixer<'a> {
+        exported: &'a HashMap<String, HashSet<String>>,
+    }
+
+    impl PathFixer<'_> {
+        fn is_exported(&self, crate_ident: &syn::Ident, name: &syn::Ident) -> bool {
+            self.exported
+                .get(&crate_ident.to_string())
+                .is_some_and(|macros| macros.contains(&name.to_string()))
+        }
+    }
+
+    impl VisitMut for PathFixer<'_> {
+        fn visit_path_mut(&mut self, i: &mut syn::Path) {
+            let segments = i.segments.iter().collect::<Vec<_>>();
+            if let [root, krate, name] = &segments[..] {
+                if root.ident == "crate" && self.is_exported(&krate.ident, &name.ident) {
+                    i.segments = [(*root).clone(), (*name).clone()].into_iter().collect();
+                }
+            }
+            visit_mut::visit_path_mut(self, i);
+        }
+
+        fn visit_item_use_mut(&mut self, i: &mut ItemUse) {
+            let UseTree::Path(root) = &mut i.tree else {
+                return;
+            };
+            if root.ident != "crate" {
+                return;
+            }
+            let UseTree::Path(krate) = &mut *root.tree else {
+                return;
+            };
+
+            let leaf_name = |tree: &UseTree| match tree {
+                UseTree::Name(name) => Some(name.ident.clone()),
+                UseTree::Rename(rename) => Some(rename.ident.clone()),
+                _ => None,
+            };
+            let is_macro = |tree: &UseTree| {
+                leaf_name(tree).is_some_and(|name| self.is_exported(&krate.ident, &name))
+            };
+
+            // `crate::<crate>::{macro_name, path}` を `crate::{<crate>::{path}, macro_name}` にする
+            let (macros, rest): (Vec<_>, Vec<_>) = match &*krate.tree {
+                UseTree::Group(group) => group.items.iter().cloned().partition(is_macro),
+                tree if is_macro(tree) => (vec![tree.clone()], vec![]),
+                _ => return,
+            };
+            if macros.is_empty() {
+                return;
+            }
+
+            let mut items = Punctuated::new();
+            if !rest.is_empty() {
+                let mut krate = krate.clone();
+                if let UseTree::Group(group) = &mut *krate.tree {
+                    group.items = rest.into_iter().collect();
+                }
+                items.push(UseTree::Path(krate));
+            }
+            items.extend(macros);
+            *root.tree = UseTree::Group(UseGroup {
+                brace_token: Default::default(),
+                items,
+            });
+        }
+    }
+
+    PathFixer {
+        exported: &exported,
+    }
+    .visit_file_mut(file);
+}
+
+/// クレートごとの `#[macro_export]` されたマクロの名前
+fn exported_macros(file: &File, crate_idents: &[String]) -> HashMap<String, HashSet<String>> {
+    struct Collector(HashSet<String>);
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_item_macro(&mut self, i: &'ast ItemMacro) {
+            if let Some(ident) = &i.ident {
+                if is_exported(i) {
+                    self.0.insert(ident.to_string());
+                }
+            }
+            visit::visit_item_macro(self, i);
+        }
+    }
+
+    let mut exported = HashMap::new();
+    for item in &file.items {
+        let Item::Mod(module) = item else {
+            continue;
+        };
+        let crate_ident = module.ident.to_string();
+        if !crate_idents.contains(&crate_ident) {
+            continue;
+        }
+
+        let mut collector = Collector(HashSet::new());
+        collector.visit_item_mod(module);
+        exported.insert(crate_ident, collector.0);
+    }
+
+    exported
+}
+
+fn is_exported(item_macro: &ItemMacro) -> bool {
+    item_macro
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("macro_export"))
+}
+
+/// `$crate::name` を `$crate::<crate_ident>::name` に書き換える。`name` が `macros` に含まれるか、
+/// 直後に `!` が続くマクロの呼び出しであれば書き換えない。
+fn prefix_dollar_crate(
+    tokens: TokenStream,
+    crate_ident: &Ident,
+    macros: &HashSet<String>,
+) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let is_punct = |index: usize, c: char| matches!(tokens.get(index), Some(TokenTree::Punct(punct)) if punct.as_char() == c);
+
+    let mut fixed = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(
+                    group.delimiter(),
+                    prefix_dollar_crate(group.stream(), crate_ident, macros),
+                );
+                new_group.set_span(group.span());
+                fixed.push(TokenTree::Group(new_group));
+            }
+            TokenTree::Ident(ident)
+                if ident == "crate"
+                    && index > 0
+                    && is_punct(index - 1, '$')
+                    && is_punct(index + 1, ':')
+                    && is_punct(index + 2, ':') =>
+            {
+                fixed.push(token.clone());
+
+                let is_macro = match tokens.get(index + 3) {
+                    Some(TokenTree::Ident(name)) => {
+                        macros.contains(&name.to_string()) || is_punct(index + 4, '!')
+                    }
+                    _ => false,
+                };
+                if !is_macro {
+                    fixed.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+                    fixed.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+                    fixed.push(TokenTree::Ident(crate_ident.clone()));
+                }
+            }
+            _ => fixed.push(token.clone()),
+        }
+    }
+
+    fixed.into_iter().collect()
+}
+
+/// `#[macro_export]` されたマクロをバンドル結果の先頭に移動する。元のクレートでの定義の順序によらず、
+/// どこからでもテキスト上のスコープで使えるようになる。
+pub fn hoist_exported_macros(file: &mut File) {
+    fn take_exported(items: &mut Vec<Item>, hoisted: &mut Vec<Item>) {
+        for item in std::mem::take(items) {
+            match item {
+                Item::Macro(item_macro)
+                    if item_macro.ident.is_some() && is_exported(&item_macro) =>
+                {
+                    hoisted.push(Item::Macro(item_macro));
+                }
+                Item::Mod(mut module) => {
+                    if let Some((_, items)) = &mut module.content {
+                        take_exported(items, hoisted);
+                    }
+                    items.push(Item::Mod(module));
+                }
+                item => items.push(item),
+            }
+        }
+    }
+
+    let mut hoisted = vec![];
+    take_exported(&mut file.items, &mut hoisted);
+    hoisted.append(&mut file.items);
+    file.items = hoisted;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    fn fixed(source: &str) -> String {
+        let mut file: File = syn::parse_str(source).unwrap();
+        fix_macro_paths(&mut file, &["library".to_string(), "io".to_string()]);
+        hoist_exported_macros(&mut file);
+        file.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn test_fix_macro_paths() {
+        let fixed = fixed(
+            r#"
+            mod library {
+                pub mod grid {
+                    pub struct Grid;
+                    #[macro_export]
+                    macro_rules! mat {
+                        () => { $crate::grid::Grid };
+                        ($e:expr) => { $crate::mat!() };
+                    }
+                    macro_rules! local {
+                        () => { $crate::grid::Grid };
+                    }
+                }
+                pub mod ij {
+                    use crate::library::{grid::Grid, mat};
+                    pub fn f() -> Grid { crate::library::mat!() }
+                }
+            }
+            mod io {
+                macro_rules! not_exported { () => { $crate::Source }; }
+            }
+            fn main() { let _ = crate::library::mat!(); }
+            "#,
+        );
+
+        assert!(fixed.starts_with("# [macro_export] macro_rules ! mat"));
+        assert!(fixed.contains("{ $ crate :: library :: grid :: Grid }"));
+        assert!(fixed.contains("{ $ crate :: mat ! () }"));
+        assert!(fixed
+            .contains("macro_rules ! local { () => { $ crate :: library :: grid :: Grid } ; }"));
+        assert!(fixed.contains("use crate :: { library :: { grid :: Grid } , mat } ;"));
+        assert!(fixed.contains("pub fn f () -> Grid { crate :: mat ! () }"));
+        assert!(fixed.contains("{ $ crate :: io :: Source }"));
+        assert!(fixed.contains("fn main () { let _ = crate :: mat ! () ; }"));
+    }
+}
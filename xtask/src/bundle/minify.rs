@@ -0,0 +1,339 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::File;
+
+// 連続する記号の間に空白を入れないと別のトークンとして字句解析されてしまう組
+const MULTI_CHAR_PUNCTS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|=",
+    "->", "=>", "<-", "::", "..", "//", "/*", "*/",
+];
+
+/// トークン列をもとに、コメントとドキュメントを取り除いて最小限の空白で出力する。
+///
+/// ソースコードの文字列ではなくトークンを扱うので、生文字列リテラルや文字リテラルの中身に影響されない。
+pub fn minify(file: &File) -> String {
+    let mut writer = Writer::default();
+    writer.write_stream(strip_doc_attributes(file.to_token_stream()));
+
+    writer.output
+}
+
+/// `#[doc = "..."]` と `#![doc = "..."]` (= ドキュメントコメント) を取り除く。
+///
+/// マクロの定義中の `#[doc = $doc]` のように値がリテラルでないものは残す。
+pub fn strip_doc_attributes(tokens: TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut stripped = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        if let TokenTree::Punct(punct) = &tokens[i] {
+            if punct.as_char() == '#' {
+                let bang =
+                    matches!(&tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '!');
+                let attr_index = i + 1 + bang as usize;
+                if let Some(TokenTree::Group(group)) = tokens.get(attr_index) {
+                    if group.delimiter() == Delimiter::Bracket && is_doc_attribute(group.stream()) {
+                        i = attr_index + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        stripped.push(match &tokens[i] {
+            TokenTree::Group(group) => {
+                let mut new_group = proc_macro2::Group::new(
+                    group.delimiter(),
+                    strip_doc_attributes(group.stream()),
+                );
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            token => token.clone(),
+        });
+        i += 1;
+    }
+
+    stripped.into_iter().collect()
+}
+
+fn is_doc_attribute(tokens: TokenStream) -> bool {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    matches!(
+        &tokens[..],
+        [TokenTree::Ident(ident), TokenTree::Punct(eq), TokenTree::Literal(_)]
+            if ident == "doc" && eq.as_char() == '='
+    )
+}
+
+/// 直前に出力したトークンの種類
+#[derive(Debug, Default)]
+enum Last {
+    /// 出力の先頭、または開き括弧の直後
+    #[default]
+    Start,
+    Word,
+    /// 末尾が `.` のリテラル (`1.` など)
+    LiteralEndingWithDot,
+    /// 連続する記号。`Joint` のものは次の記号と続けて 1 つのトークンになる
+    Punct {
+        run: String,
+        spacing: Spacing,
+    },
+    Close,
+}
+
+#[derive(Debug, Default)]
+struct Writer {
+    output: String,
+    last: Last,
+}
+
+impl Writer {
+    fn write_stream(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            self.write_token(token);
+        }
+    }
+
+    fn write_token(&mut self, token: TokenTree) {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => {
+                        self.write_stream(group.stream());
+                        return;
+                    }
+                };
+
+                self.output += open;
+                self.last = Last::Start;
+                self.write_stream(group.stream());
+                self.output += close;
+                self.last = Last::Close;
+            }
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+                if self.needs_space_before_word() {
+                    self.output.push(' ');
+                }
+                self.output += &ident;
+                self.last = Last::Word;
+            }
+            TokenTree::Literal(literal) => {
+                let literal = literal.to_string();
+                if self.needs_space_before_word() {
+                    self.output.push(' ');
+                }
+                self.output += &literal;
+                self.last = if literal.ends_with('.') {
+                    Last::LiteralEndingWithDot
+                } else {
+                    Last::Word
+                };
+            }
+            TokenTree::Punct(punct) => {
+                let c = punct.as_char();
+                let mut run = match std::mem::take(&mut self.last) {
+                    Last::Punct {
+                        run,
+                        spacing: Spacing::Joint,
+                    } => run,
+                    Last::Punct {
+                        run,
+                        spacing: Spacing::Alone,
+                    } => {
+                        if forms_multi_char_punct(&run, c) {
+                            self.output.push(' ');
+                        }
+                        String::new()
+                    }
+                    // `foo#` や `foo'` は予約された接頭辞として扱われる
+                    Last::Word if c == '#' || c == '\'' => {
+                        self.output.push(' ');
+                        String::new()
+                    }
+                    Last::LiteralEndingWithDot if c == '.' => {
+                        self.output.push(' ');
+                        String::new()
+                    }
+                    _ => String::new(),
+                };
+
+                self.output.push(c);
+                run.push(c);
+                self.last = Last::Punct {
+                    run,
+                    spacing: punct.spacing(),
+                };
+            }
+        }
+    }
+
+    fn needs_space_before_word(&self) -> bool {
+        match &self.last {
+            Last::Word | Last::LiteralEndingWithDot => true,
+            // 生識別子と生文字列リテラルは 1 つのトークンなので、`#` の後に識別子が続いても問題ない
+            Last::Start | Last::Punct { .. } | Last::Close => false,
+        }
+    }
+}
+
+/// 記号の並び `run` の直後に `next` を続けて書くと、境界をまたいだ記号として字句解析されるか。
+fn forms_multi_char_punct(run: &str, next: char) -> bool {
+    MULTI_CHAR_PUNCTS.iter().any(|punct| {
+        punct
+            .char_indices()
+            .skip(1)
+            .any(|(i, c)| c == next && run.ends_with(&punct[..i]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::{check_compile_in, expand_crate, expand_in, macros, JUDGE_EDITION};
+    use std::{path::Path, process::Command};
+
+    /// 空白の有無 (`Spacing`) を無視してトークンを比較するための文字列の列
+    fn token_texts(tokens: TokenStream) -> Vec<String> {
+        let mut texts = vec![];
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => {
+                    texts.push(format!("{:?}", group.delimiter()));
+                    texts.extend(token_texts(group.stream()));
+                    texts.push("end".to_string());
+                }
+                token => texts.push(token.to_string()),
+            }
+        }
+
+        texts
+    }
+
+    const TRICKY_SOURCE: &str = r####"
+        //! crate documentation
+        use std::fmt::Debug;
+
+        /// a struct with a lifetime
+        #[derive(Debug)]
+        pub struct Wrapper<'a> {
+            /// field documentation
+            pub text: &'a str,
+        }
+
+        macro_rules! documented {
+            ($(#[$meta:meta])* $name:ident) => {
+                $(#[$meta])*
+                pub fn $name() -> &'static str { stringify!($name) }
+            };
+        }
+
+        documented! {
+            /// documentation passed to a macro
+            generated
+        }
+
+        fn longest<'a, 'b: 'a>(a: &'a str, b: &'b str) -> &'a str {
+            if a.len() >= b.len() { a } else { b }
+        }
+
+        pub fn run() -> Vec<String> {
+            let raw = r#"he said "// not a comment" /* nor this */"#;
+            let quote = '"';
+            let escaped = "a \" // b \\";
+            let bytes = br##"#"raw bytes"#"##;
+            let range = 1..=3;
+            let shifted = 1 << 2 >> 1;
+            let negated = -(-1i32);
+            let not = !!true;
+            let float = 1. ..2.;
+            let tuple = ((1, 2), 3);
+            let nested = tuple.0.1;
+            let deref = &&&1;
+            let wrapper = Wrapper { text: longest(raw, escaped) };
+            let lifetime_bound: Box<dyn Debug + 'static> = Box::new(quote);
+            vec![
+                format!("{wrapper:?}"),
+                format!("{quote} {bytes:?} {range:?} {shifted} {negated} {not}"),
+                format!("{float:?} {nested} {deref} {lifetime_bound:?} {}", generated()),
+            ]
+        }
+    "####;
+
+    #[test]
+    fn test_minify_is_token_preserving() {
+        let file: File = syn::parse_str(TRICKY_SOURCE).unwrap();
+        let minified = minify(&file);
+
+        assert!(!minified.contains("documentation"));
+        assert!(minified.contains(r##"r#"he said "// not a comment" /* nor this */"#"##));
+        let reparsed: File = syn::parse_str(&minified).unwrap();
+        assert_eq!(
+            token_texts(reparsed.to_token_stream()),
+            token_texts(strip_doc_attributes(file.to_token_stream()))
+        );
+    }
+
+    #[test]
+    fn test_minified_source_compiles() {
+        let file: File = syn::parse_str(TRICKY_SOURCE).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("lib.rs");
+        std::fs::write(&source_path, minify(&file)).unwrap();
+
+        let output = Command::new("rustc")
+            .args([
+                "--edition",
+                JUDGE_EDITION,
+                "--crate-type",
+                "lib",
+                "--emit",
+                "metadata",
+            ])
+            .arg("--out-dir")
+            .arg(temp_dir.path())
+            .arg(&source_path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn test_minify_workspace_crates() {
+        let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        for crate_name in ["library", "types", "io", "referee", "solutions"] {
+            let path = workspace_root.join(crate_name).join("src").join("lib.rs");
+            let (file, _) = expand_crate(Some(crate_name), &path, false).unwrap();
+            let minified = minify(&file);
+
+            // 同じトークン列に字句解析されるので、元のソースと同じようにコンパイルされる
+            let reparsed: File = syn::parse_str(&minified)
+                .unwrap_or_else(|e| panic!("failed to parse minified `{crate_name}`: {e}"));
+            assert_eq!(
+                token_texts(reparsed.to_token_stream()),
+                token_texts(strip_doc_attributes(file.to_token_stream())),
+                "minified `{crate_name}` differs from the original"
+            );
+        }
+    }
+
+    #[test]
+    fn test_minified_bundle_compiles() {
+        // 空白の入れ方はトークン列の比較では確かめられないので、バンドル全体を実際にコンパイルする
+        let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let entry_path = workspace_root.join("driver").join("src").join("main.rs");
+        let mut file = expand_in(workspace_root, &entry_path, false).unwrap();
+        macros::hoist_exported_macros(&mut file);
+
+        check_compile_in(workspace_root, &minify(&file)).unwrap();
+    }
+}
@@ -0,0 +1,741 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use syn::{
+    Attribute, File, Item, ItemImpl, ItemMacro, ItemUse, Meta, NestedMeta, TraitItem, UseTree,
+};
+
+// 到達可能性は名前だけで判定する。同名の別アイテムも到達可能とみなすので残しすぎることはあるが、
+// パス解決をしない分、使われているアイテムを消してしまうことはない。
+
+/// バンドル結果から `main.rs` のアイテムから到達できないモジュールとアイテムを取り除く。取り除いた
+/// アイテムの数を返す。
+pub fn prune(file: &mut File) -> usize {
+    remove_test_items(&mut file.items);
+
+    let mut graph = Graph::default();
+    graph.collect(&file.items, None);
+    graph.resolve();
+
+    let mut next_id = 0;
+    let before = graph.nodes.len();
+    file.items = graph.retain(std::mem::take(&mut file.items), &mut next_id);
+    before - graph.nodes.iter().filter(|node| node.kept).count()
+}
+
+/// `#[cfg(test)]` のアイテムは提出時には使われないので先に取り除いておく。
+fn remove_test_items(items: &mut Vec<Item>) {
+    items.retain(|item| !item_attrs(item).is_some_and(|attrs| attrs.iter().any(is_cfg_test)));
+    for item in items {
+        if let Item::Mod(module) = item {
+            if let Some((_, items)) = &mut module.content {
+                remove_test_items(items);
+            }
+        }
+    }
+}
+
+fn is_cfg_test(attr: &Attribute) -> bool {
+    if !attr.path.is_ident("cfg") {
+        return false;
+    }
+
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => {
+            list.nested.len() == 1
+                && matches!(&list.nested[0], NestedMeta::Meta(Meta::Path(path)) if path.is_ident("test"))
+        }
+        _ => false,
+    }
+}
+
+fn item_attrs(item: &Item) -> Option<&Vec<Attribute>> {
+    Some(match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::ExternCrate(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::ForeignMod(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Macro2(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::TraitAlias(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => return None,
+    })
+}
+
+#[derive(Debug)]
+enum Kind {
+    /// 常に残すアイテム
+    Root,
+    /// 定義する名前のどれかが到達可能なら残すアイテム
+    Named,
+    /// 実装対象の型が到達可能で、かつトレイトがローカルなら到達可能なときに残す impl
+    Impl {
+        trait_name: Option<String>,
+        /// 実装対象の型とトレイトの型引数に現れる名前。`impl From<Local> for f64` も `Local` がなければ
+        /// 不要になる
+        type_names: Vec<String>,
+    },
+    /// 要素ごとに残すかどうかを決める use
+    Use { leaves: Vec<UseLeaf> },
+    /// 中のアイテムが残るか、名前が到達可能なら残すモジュール
+    Module,
+}
+
+#[derive(Debug)]
+struct UseLeaf {
+    /// インポートされる名前。`None` はグロブ
+    name: Option<String>,
+    /// インポート元のパスに含まれる名前
+    path: Vec<String>,
+    kept: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    kind: Kind,
+    /// このアイテムが定義する名前
+    defines: Vec<String>,
+    /// このアイテムが残るときに到達可能になる名前
+    references: HashSet<String>,
+    /// 親モジュールのノード
+    parent: Option<usize>,
+    kept: bool,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    /// ローカルで定義されたトレイトの名前
+    local_traits: HashSet<String>,
+    /// ローカルで定義された型とトレイトの名前
+    local_types: HashSet<String>,
+    /// macro_rules! の名前と、その展開結果が定義しうるアイテムの名前
+    macro_definitions: HashMap<String, Vec<String>>,
+    /// バンドル内で定義された名前
+    local_names: HashSet<String>,
+    reachable: HashSet<String>,
+}
+
+impl Graph {
+    /// アイテムをノードとして集める。ノードの順序は `retain` で走査する順序と一致させること。
+    fn collect(&mut self, items: &[Item], parent: Option<usize>) {
+        for item in items {
+            let id = self.nodes.len();
+            let (kind, defines, references) = match item {
+                // モジュール直下でない (= main.rs の) アイテムは起点。ただし use は要素ごとに判定する
+                _ if parent.is_none() && !matches!(item, Item::Mod(_) | Item::Use(_)) => {
+                    (Kind::Root, vec![], idents_in(item.to_token_stream()))
+                }
+                Item::Mod(module) => (Kind::Module, vec![module.ident.to_string()], HashSet::new()),
+                Item::Impl(item_impl) => self.impl_node(item_impl),
+                Item::Use(item_use) => use_node(item_use),
+                Item::Macro(item_macro) => self.macro_node(item_macro),
+                Item::Trait(item_trait) => {
+                    let name = item_trait.ident.to_string();
+                    self.local_traits.insert(name.clone());
+                    self.local_types.insert(name.clone());
+
+                    // メソッド呼び出し構文で使われるトレイトも残すため、メソッドなどの名前も定義とみなす
+                    let mut defines = vec![name];
+                    defines.extend(item_trait.items.iter().filter_map(|item| match item {
+                        TraitItem::Const(item) => Some(item.ident.to_string()),
+                        TraitItem::Method(item) => Some(item.sig.ident.to_string()),
+                        TraitItem::Type(item) => Some(item.ident.to_string()),
+                        _ => None,
+                    }));
+                    (Kind::Named, defines, idents_in(item.to_token_stream()))
+                }
+                _ => match named_item_ident(item) {
+                    Some(name) => {
+                        if matches!(
+                            item,
+                            Item::Struct(_) | Item::Enum(_) | Item::Union(_) | Item::Type(_)
+                        ) {
+                            self.local_types.insert(name.clone());
+                        }
+                        (Kind::Named, vec![name], idents_in(item.to_token_stream()))
+                    }
+                    None => (Kind::Root, vec![], idents_in(item.to_token_stream())),
+                },
+            };
+
+            self.local_names.extend(defines.iter().cloned());
+            self.nodes.push(Node {
+                kind,
+                defines,
+                references,
+                parent,
+                kept: false,
+            });
+
+            if let Item::Mod(module) = item {
+                if let Some((_, items)) = &module.content {
+                    self.collect(items, Some(id));
+                }
+            }
+        }
+    }
+
+    fn impl_node(&self, item_impl: &ItemImpl) -> (Kind, Vec<String>, HashSet<String>) {
+        let trait_name = item_impl.trait_.as_ref().and_then(|(_, path, _)| {
+            path.segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+        });
+        // ローカルの型かどうかは全体を集め終わるまでわからないので、判定は resolve で行う
+        let mut type_names = idents_in(item_impl.self_ty.to_token_stream());
+        if let Some((_, path, _)) = &item_impl.trait_ {
+            if let Some(segment) = path.segments.last() {
+                type_names.extend(idents_in(segment.arguments.to_token_stream()));
+            }
+        }
+
+        (
+            Kind::Impl {
+                trait_name,
+                type_names: type_names.into_iter().collect(),
+            },
+            vec![],
+            idents_in(item_impl.to_token_stream()),
+        )
+    }
+
+    fn macro_node(&mut self, item_macro: &ItemMacro) -> (Kind, Vec<String>, HashSet<String>) {
+        let references = idents_in(item_macro.to_token_stream());
+
+        // macro_rules! による定義
+        if let Some(ident) = &item_macro.ident {
+            let name = ident.to_string();
+            self.macro_definitions.insert(
+                name.clone(),
+                defined_names_in(item_macro.mac.tokens.clone()),
+            );
+            return (Kind::Named, vec![name], references);
+        }
+
+        // アイテム位置でのマクロ呼び出し。定義するアイテムの名前はわからないので、引数に現れる名前と
+        // マクロ定義中でアイテムとして定義されている名前を定義とみなす。マクロ定義が後に現れる場合に
+        // 備えて、定義側の名前は resolve で加える。`thread_local! { static NAME: ... }` のように引数が
+        // アイテムの形をしていれば、そのアイテムの名前だけを定義とみなす。
+        let item_names = top_level_item_names(item_macro.mac.tokens.clone());
+        let defines = if item_names.is_empty() {
+            idents_in(item_macro.mac.tokens.clone())
+                .into_iter()
+                .collect()
+        } else {
+            item_names
+        };
+        (Kind::Named, defines, references)
+    }
+
+    /// 到達可能な名前が増えなくなるまで、残すノードを増やしていく。
+    fn resolve(&mut self) {
+        // マクロ呼び出しの定義に、マクロ定義中のアイテムの名前を加える
+        for node in &mut self.nodes {
+            if node.parent.is_none() {
+                continue;
+            }
+            if let Some(names) = node
+                .references
+                .iter()
+                .find_map(|name| self.macro_definitions.get(name))
+            {
+                if !node
+                    .defines
+                    .iter()
+                    .any(|name| self.macro_definitions.contains_key(name))
+                {
+                    node.defines.extend(names.iter().cloned());
+                }
+            }
+        }
+
+        loop {
+            let mut changed = false;
+            for id in 0..self.nodes.len() {
+                changed |= self.visit(id);
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// ノードを残すべきか判定し、残すなら参照している名前を到達可能にする。変化があれば true を返す。
+    fn visit(&mut self, id: usize) -> bool {
+        let node = &self.nodes[id];
+        if let Kind::Use { leaves } = &node.kind {
+            return self.visit_use(id, leaves.len());
+        }
+        if node.kept {
+            return false;
+        }
+
+        let keep = match &node.kind {
+            Kind::Root => true,
+            Kind::Named | Kind::Module => node
+                .defines
+                .iter()
+                .any(|name| self.reachable.contains(name)),
+            Kind::Impl {
+                trait_name,
+                type_names,
+            } => {
+                let trait_reachable = trait_name.as_ref().is_none_or(|name| {
+                    !self.local_traits.contains(name) || self.reachable.contains(name)
+                });
+                let mut local_type_names = type_names
+                    .iter()
+                    .filter(|name| self.local_types.contains(*name))
+                    .peekable();
+                let types_reachable = local_type_names.peek().is_none()
+                    || local_type_names.any(|name| self.reachable.contains(name));
+                trait_reachable && types_reachable
+            }
+            Kind::Use { .. } => unreachable!(),
+        };
+        if !keep {
+            return false;
+        }
+
+        self.keep(id);
+        let node = &self.nodes[id];
+        self.reachable.extend(node.references.iter().cloned());
+        if !matches!(node.kind, Kind::Module) {
+            self.reachable.extend(node.defines.iter().cloned());
+        }
+
+        true
+    }
+
+    fn visit_use(&mut self, id: usize, num_leaves: usize) -> bool {
+        // use のためだけにモジュールを残すと、インポートしかない空のモジュールが残ってしまう。モジュール
+        // の中の use は、モジュールが他のアイテムによって残るときだけ残す
+        let parent_kept = self.nodes[id]
+            .parent
+            .is_none_or(|parent| self.nodes[parent].kept);
+        if !parent_kept {
+            return false;
+        }
+
+        let mut changed = false;
+        for index in 0..num_leaves {
+            let Kind::Use { leaves } = &self.nodes[id].kind else {
+                unreachable!()
+            };
+            let leaf = &leaves[index];
+            if leaf.kept {
+                continue;
+            }
+
+            // グロブと外部のクレートからのインポートは親モジュールが残るなら残す。外部のトレイトは
+            // メソッド呼び出し構文でしか使われないことがあり、名前からは使われているかわからない。
+            let keep = match &leaf.name {
+                Some(name) if !self.is_external(&leaf.path) => self.reachable.contains(name),
+                _ => true,
+            };
+            if !keep {
+                continue;
+            }
+
+            let path = leaf.path.clone();
+            if let Kind::Use { leaves } = &mut self.nodes[id].kind {
+                leaves[index].kept = true;
+            }
+            self.reachable.extend(path);
+            self.nodes[id].kept = true;
+            changed = true;
+        }
+
+        changed
+    }
+
+    fn is_external(&self, path: &[String]) -> bool {
+        path.first().is_some_and(|root| {
+            !matches!(&**root, "crate" | "self" | "super" | "Self")
+                && !self.local_names.contains(root)
+        })
+    }
+
+    /// ノードとその祖先のモジュールを残す。
+    fn keep(&mut self, id: usize) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            self.nodes[id].kept = true;
+            current = self.nodes[id].parent;
+        }
+    }
+
+    /// 残すノードに対応するアイテムだけを残す。
+    fn retain(&self, items: Vec<Item>, next_id: &mut usize) -> Vec<Item> {
+        let mut retained = vec![];
+        for mut item in items {
+            let id = *next_id;
+            *next_id += 1;
+
+            let node = &self.nodes[id];
+            if let Item::Mod(module) = &mut item {
+                if let Some((_, items)) = &mut module.content {
+                    *items = self.retain(std::mem::take(items), next_id);
+                }
+            }
+            if !node.kept {
+                continue;
+            }
+
+            if let (Item::Use(item_use), Kind::Use { leaves }) = (&mut item, &node.kind) {
+                let mut leaves = leaves.iter();
+                match retain_use_tree(item_use.tree.clone(), &mut leaves) {
+                    Some(tree) => item_use.tree = tree,
+                    None => continue,
+                }
+            }
+
+            retained.push(item);
+        }
+
+        retained
+    }
+}
+
+fn named_item_ident(item: &Item) -> Option<String> {
+    let ident = match item {
+        Item::Const(item) => &item.ident,
+        Item::Enum(item) => &item.ident,
+        Item::Fn(item) => &item.sig.ident,
+        Item::Macro2(item) => &item.ident,
+        Item::Static(item) => &item.ident,
+        Item::Struct(item) => &item.ident,
+        Item::TraitAlias(item) => &item.ident,
+        Item::Type(item) => &item.ident,
+        Item::Union(item) => &item.ident,
+        _ => return None,
+    };
+
+    Some(ident.to_string())
+}
+
+fn use_node(item_use: &ItemUse) -> (Kind, Vec<String>, HashSet<String>) {
+    let mut leaves = vec![];
+    collect_use_leaves(&item_use.tree, &mut vec![], &mut leaves);
+
+    (Kind::Use { leaves }, vec![], HashSet::new())
+}
+
+/// use の木の葉を左から順に集める。順序は `retain_use_tree` と一致させること。
+fn collect_use_leaves(tree: &UseTree, path: &mut Vec<String>, leaves: &mut Vec<UseLeaf>) {
+    match tree {
+        UseTree::Path(use_path) => {
+            path.push(use_path.ident.to_string());
+            collect_use_leaves(&use_path.tree, path, leaves);
+            path.pop();
+        }
+        UseTree::Name(use_name) => {
+            let mut leaf_path = path.clone();
+            let name = if use_name.ident == "self" {
+                // `a::{self}` は `a` をインポートする
+                path.last().cloned().unwrap_or_default()
+            } else {
+                leaf_path.push(use_name.ident.to_string());
+                use_name.ident.to_string()
+            };
+            leaves.push(UseLeaf {
+                name: Some(name),
+                path: leaf_path,
+                kept: false,
+            });
+        }
+        UseTree::Rename(use_rename) => {
+            let mut leaf_path = path.clone();
+            leaf_path.push(use_rename.ident.to_string());
+            leaves.push(UseLeaf {
+                name: Some(use_rename.rename.to_string()),
+                path: leaf_path,
+                kept: false,
+            });
+        }
+        UseTree::Glob(_) => leaves.push(UseLeaf {
+            name: None,
+            path: path.clone(),
+            kept: false,
+        }),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_use_leaves(tree, path, leaves);
+            }
+        }
+    }
+}
+
+/// 残す葉だけからなる use の木を作る。何も残らなければ `None` を返す。
+fn retain_use_tree<'a>(
+    tree: UseTree,
+    leaves: &mut impl Iterator<Item = &'a UseLeaf>,
+) -> Option<UseTree> {
+    match tree {
+        UseTree::Path(mut use_path) => {
+            let tree = retain_use_tree(*use_path.tree, leaves)?;
+            use_path.tree = Box::new(tree);
+            Some(UseTree::Path(use_path))
+        }
+        UseTree::Group(mut group) => {
+            let items = std::mem::take(&mut group.items);
+            // 先にすべての子を走査して葉の順序を保つ
+            let retained = items
+                .into_iter()
+                .filter_map(|tree| retain_use_tree(tree, leaves))
+                .collect::<Vec<_>>();
+            if retained.is_empty() {
+                return None;
+            }
+            group.items = retained.into_iter().collect();
+            Some(UseTree::Group(group))
+        }
+        leaf @ (UseTree::Name(_) | UseTree::Rename(_) | UseTree::Glob(_)) => {
+            let kept = leaves.next().expect("use leaves out of sync").kept;
+            kept.then_some(leaf)
+        }
+    }
+}
+
+/// トークン列に現れる識別子を集める。フォーマット文字列中で `{name}` として参照される名前も含める。
+fn idents_in(tokens: TokenStream) -> HashSet<String> {
+    let mut idents = HashSet::new();
+    collect_idents(tokens, &mut idents);
+
+    idents
+}
+
+fn collect_idents(tokens: TokenStream, idents: &mut HashSet<String>) {
+    static FORMAT_ARG: once_cell::sync::Lazy<Regex> =
+        once_cell::sync::Lazy::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)").unwrap());
+
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                idents.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            TokenTree::Literal(literal) => {
+                let literal = literal.to_string();
+                if literal.contains('"') {
+                    idents.extend(
+                        FORMAT_ARG
+                            .captures_iter(&literal)
+                            .map(|captures| captures[1].to_string()),
+                    );
+                }
+            }
+            TokenTree::Punct(_) => {}
+        }
+    }
+}
+
+/// macro_rules! の定義から、展開結果のトップレベルでアイテムとして定義されている名前 (`fn name` の
+/// `name` など) を集める。
+fn defined_names_in(tokens: TokenStream) -> Vec<String> {
+    // 規則は `(パターン) => { 展開結果 };` の並び
+    let mut names = vec![];
+    let mut previous = None;
+    for token in tokens {
+        if let TokenTree::Group(group) = &token {
+            if matches!(&previous, Some(TokenTree::Punct(punct)) if punct.as_char() == '>') {
+                names.extend(top_level_item_names(group.stream()));
+            }
+        }
+        previous = Some(token);
+    }
+
+    names
+}
+
+/// トップレベルでアイテムとして定義されている名前を集める。impl の中の `new` のように入れ子になった
+/// 定義は、展開先のモジュールに名前を定義しないので含めない。繰り返し `$(...)*` の中は含める。
+fn top_level_item_names(tokens: TokenStream) -> Vec<String> {
+    const ITEM_KEYWORDS: &[&str] = &[
+        "const", "enum", "fn", "mod", "static", "struct", "trait", "type", "union",
+    ];
+
+    let mut names = vec![];
+    let mut after_keyword = false;
+    let mut after_dollar = false;
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+                if after_keyword {
+                    names.push(ident.clone());
+                }
+                after_keyword = ITEM_KEYWORDS.contains(&&*ident);
+                after_dollar = false;
+            }
+            TokenTree::Group(group) => {
+                if after_dollar {
+                    names.extend(top_level_item_names(group.stream()));
+                }
+                after_keyword = false;
+                after_dollar = false;
+            }
+            TokenTree::Punct(punct) => {
+                after_keyword = false;
+                after_dollar = punct.as_char() == '$';
+            }
+            TokenTree::Literal(_) => {
+                after_keyword = false;
+                after_dollar = false;
+            }
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::expand_in;
+    use std::path::Path;
+
+    fn pruned(source: &str) -> String {
+        let mut file: File = syn::parse_str(source).unwrap();
+        prune(&mut file);
+        file.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn test_prune_items() {
+        let pruned = pruned(
+            r#"
+            mod library {
+                pub mod a {
+                    pub struct Used;
+                    impl Used { pub fn new() -> Self { Used } }
+                    pub struct Unused;
+                    impl super::Marker for Unused {}
+                    impl std::fmt::Display for Used {
+                        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) }
+                    }
+                }
+                pub mod b { pub fn g() {} }
+                pub trait Marker {}
+                #[cfg(test)]
+                mod tests { use super::a::Used; }
+            }
+            use library::a::{Unused, Used};
+            fn main() { let _ = Used::new(); }
+            "#,
+        );
+
+        assert!(pruned.contains("pub struct Used"));
+        assert!(pruned.contains("impl Used"));
+        assert!(pruned.contains("impl std :: fmt :: Display for Used"));
+        assert!(pruned.contains("use library :: a :: { Used }"));
+        assert!(!pruned.contains("Unused"));
+        assert!(!pruned.contains("Marker"));
+        assert!(!pruned.contains("mod b"));
+        assert!(!pruned.contains("mod tests"));
+    }
+
+    #[test]
+    fn test_prune_keeps_traits_used_by_methods() {
+        let pruned = pruned(
+            r#"
+            mod io {
+                pub trait WriteOutput { fn write_to(&self); }
+                impl WriteOutput for crate::Output { fn write_to(&self) {} }
+                pub trait Unused { fn unused(&self); }
+            }
+            use io::WriteOutput;
+            struct Output;
+            fn main() { Output.write_to(); }
+            "#,
+        );
+
+        assert!(pruned.contains("pub trait WriteOutput"));
+        assert!(pruned.contains("impl WriteOutput for crate :: Output"));
+        assert!(!pruned.contains("Unused"));
+    }
+
+    #[test]
+    fn test_prune_keeps_external_imports() {
+        let pruned = pruned(
+            r#"
+            mod library {
+                pub mod a {
+                    use itertools::Itertools;
+                    use super::b::Unused;
+                    pub fn joined(v: &[i32]) -> String { v.iter().join(" ") }
+                }
+                pub mod b { pub struct Unused; }
+            }
+            fn main() { library::a::joined(&[]); }
+            "#,
+        );
+
+        assert!(pruned.contains("use itertools :: Itertools"));
+        assert!(!pruned.contains("Unused"));
+    }
+
+    #[test]
+    fn test_prune_keeps_items_defined_by_macros() {
+        let pruned = pruned(
+            r#"
+            mod solutions {
+                macro_rules! define {
+                    ($name:ident) => { pub fn create() -> $name { $name } };
+                }
+                pub struct Naive;
+                pub struct Other;
+                define!(Naive);
+            }
+            fn main() { solutions::create(); }
+            "#,
+        );
+
+        assert!(pruned.contains("define ! (Naive)"));
+        assert!(pruned.contains("pub struct Naive"));
+        assert!(!pruned.contains("Other"));
+    }
+
+    #[test]
+    fn test_prune_library_for_simann_only_solution() {
+        let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry_path = temp_dir.path().join("main.rs");
+        std::fs::write(
+            &entry_path,
+            r#"
+            extern crate library;
+            use library::simann::{SimAnn, SimAnnMode};
+            use std::time::Duration;
+            fn main() {
+                let simann = SimAnn::new(SimAnnMode::Maximize, Duration::from_secs(1), 1.0, 0.0);
+                while simann.should_keep_trying() {}
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut file = expand_in(workspace_root, &entry_path, false).unwrap();
+        prune(&mut file);
+        let pruned = file.to_token_stream().to_string();
+
+        assert!(pruned.contains("pub struct SimAnn"));
+        // `make_per!` の展開結果の `new` などで ij が残ったり、use だけの bs が残ったりしない
+        assert!(!pruned.contains("mod ij"));
+        assert!(!pruned.contains("make_per"));
+        assert!(!pruned.contains("mod bs"));
+        assert!(!pruned.contains("OrderedFloat"));
+    }
+}
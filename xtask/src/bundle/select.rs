@@ -0,0 +1,135 @@
+use itertools::Itertools;
+use miette::{bail, miette, IntoDiagnostic, Result, WrapErr};
+use quote::quote;
+use std::collections::{HashMap, HashSet};
+use syn::{punctuated::Punctuated, token::Comma, File, Item, Path, UseTree};
+
+use crate::sources::SolutionModule;
+
+/// バンドル結果の `solutions` モジュールから `name` 以外の解法を取り除き、`define_solutions!` に
+/// `name` だけを登録する。登録される解法が 1 つになるので、引数なしで実行すると必ずこの解法が使われる。
+pub fn select_solution(
+    file: &mut File,
+    modules: &HashMap<String, SolutionModule>,
+    name: &str,
+) -> Result<()> {
+    let Some(selected) = modules.get(name) else {
+        let names = modules.keys().sorted().join(", ");
+        bail!("unknown solution `{name}` (available: {names})");
+    };
+
+    let solutions = file
+        .items
+        .iter_mut()
+        .find_map(|item| match item {
+            Item::Mod(module) if module.ident == "solutions" => module.content.as_mut(),
+            _ => None,
+        })
+        .ok_or_else(|| miette!("the bundle does not contain the `solutions` crate"))?;
+
+    // 選んだ解法と同じモジュールにある他の解法は、登録されなくなるので枝刈りで取り除かれる
+    let removed_modules = modules
+        .values()
+        .map(|module| &module.ident)
+        .filter(|ident| **ident != selected.ident)
+        .collect::<HashSet<_>>();
+    solutions.1.retain(|item| match item {
+        Item::Mod(module) => !removed_modules.contains(&module.ident.to_string()),
+        Item::Use(item_use) => {
+            !use_root(&item_use.tree).is_some_and(|root| removed_modules.contains(&root))
+        }
+        _ => true,
+    });
+
+    let define_solutions = solutions
+        .1
+        .iter_mut()
+        .find_map(|item| match item {
+            Item::Macro(item_macro) if item_macro.mac.path.is_ident("define_solutions") => {
+                Some(&mut item_macro.mac)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| miette!("`define_solutions!` is not found in the `solutions` crate"))?;
+    let registered = define_solutions
+        .parse_body_with(Punctuated::<Path, Comma>::parse_terminated)
+        .into_diagnostic()
+        .wrap_err("failed to parse `define_solutions!`")?;
+    let solution = registered
+        .into_iter()
+        .find(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == selected.type_ident)
+        })
+        .ok_or_else(|| {
+            miette!(
+                "`{}` is not registered in `define_solutions!`",
+                selected.type_ident
+            )
+        })?;
+    define_solutions.tokens = quote!(#solution);
+
+    Ok(())
+}
+
+/// `use` がインポートする `solutions` クレート直下のモジュール
+fn use_root(tree: &UseTree) -> Option<String> {
+    match tree {
+        UseTree::Path(path)
+            if matches!(&*path.ident.to_string(), "crate" | "self" | "solutions") =>
+        {
+            use_root(&path.tree)
+        }
+        UseTree::Path(path) => Some(path.ident.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+    use std::path::PathBuf;
+
+    fn module(ident: &str, type_ident: &str) -> SolutionModule {
+        SolutionModule {
+            ident: ident.to_string(),
+            files: vec![PathBuf::from(format!("solutions/src/{ident}.rs"))],
+            type_ident: type_ident.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_select_solution() {
+        let mut file: File = syn::parse_str(
+            r#"
+            mod solutions {
+                pub mod naive { pub struct NaiveSolution; }
+                pub mod greedy { pub struct GreedySolution; pub struct GreedyVariant; }
+                use naive::NaiveSolution;
+                use crate::solutions::greedy::{GreedySolution, GreedyVariant};
+                define_solutions![NaiveSolution, GreedySolution, GreedyVariant];
+            }
+            fn main() {}
+            "#,
+        )
+        .unwrap();
+        let modules = HashMap::from([
+            ("naive".to_string(), module("naive", "NaiveSolution")),
+            ("greedy".to_string(), module("greedy", "GreedySolution")),
+            ("variant".to_string(), module("greedy", "GreedyVariant")),
+        ]);
+
+        select_solution(&mut file, &modules, "variant").unwrap();
+        let selected = file.to_token_stream().to_string();
+        assert!(!selected.contains("naive"));
+        assert!(selected.contains("pub mod greedy"));
+        assert!(selected.contains("define_solutions ! [GreedyVariant]"));
+
+        let error = select_solution(&mut file, &modules, "beam").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("available: greedy, naive, variant"));
+    }
+}
@@ -0,0 +1,525 @@
+use miette::{miette, IntoDiagnostic, Result, WrapErr};
+use once_cell::sync::Lazy;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use regex::{Captures, Regex};
+use std::{collections::HashMap, fmt, fs, path::Path};
+use syn::{
+    parse_quote,
+    spanned::Spanned,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Attribute, Expr, File, ImplItem, Item, Lit, Meta, Stmt, TraitItem,
+};
+
+/// `xtask bundle --annotate` が対応表を書き出すファイル
+pub const MAP_PATH: &str = "target/bundle.map";
+
+// 整形前にアイテムや文に付けておき、整形後に取り除く目印。整形でアイテムの行が変わっても追跡できる。
+const SOURCE_MARKER: &str = "@@src ";
+// モジュールの境界を表す目印。整形後にコメントに置き換える。
+const MODULE_MARKER: &str = "@@from ";
+
+static MARKER_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"#\[doc = "@@(src|from) ([^"]*)"\]\s*"#).unwrap());
+
+/// 元のソースコードの位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, b: &mut fmt::Formatter) -> fmt::Result {
+        write!(b, "{}:{}", self.path, self.line)
+    }
+}
+
+impl Location {
+    fn parse(s: &str) -> Option<Location> {
+        let (path, line) = s.rsplit_once(':')?;
+
+        Some(Location {
+            path: path.to_string(),
+            line: line.parse().ok()?,
+        })
+    }
+}
+
+/// 目印の付いたアイテムや文
+struct MarkedNode {
+    /// 先頭の行の元の位置
+    location: Location,
+    /// 先頭の行の出力上の 0-indexed の行
+    output_line: usize,
+    /// 最後の行の整形済みのソースコード上の 1-indexed の行
+    end: usize,
+    /// 元のソースコードと行数が同じか。整形で折り返しが変わっていれば、途中の行の位置はわからない
+    same_lines: bool,
+}
+
+impl MarkedNode {
+    fn locate(&self, output_line: usize) -> Option<Location> {
+        let offset = output_line - self.output_line;
+        (offset == 0 || self.same_lines).then(|| Location {
+            path: self.location.path.clone(),
+            line: self.location.line + offset,
+        })
+    }
+}
+
+/// `path:start:end` の形の目印を、先頭の行の位置と元の最後の行に分ける。
+fn parse_source_marker(s: &str) -> Option<(Location, usize)> {
+    let (location, end) = s.rsplit_once(':')?;
+
+    Some((Location::parse(location)?, end.parse().ok()?))
+}
+
+/// `path` からパースしたアイテムと文に、元の行を表す目印を付ける。
+pub fn mark_items(file: &mut File, path: &Path) {
+    struct Marker {
+        path: String,
+    }
+
+    impl Marker {
+        /// 先頭と最後の行を目印にする。
+        fn mark(&self, attrs: &mut Vec<Attribute>, span: Span) {
+            let marker = format!(
+                "{SOURCE_MARKER}{}:{}:{}",
+                self.path,
+                span.start().line,
+                span.end().line
+            );
+            attrs.insert(0, parse_quote!(#[doc = #marker]));
+        }
+    }
+
+    impl VisitMut for Marker {
+        fn visit_item_mut(&mut self, item: &mut Item) {
+            let span = item.span();
+            if let Some(attrs) = item_attrs_mut(item) {
+                self.mark(attrs, span);
+            }
+            visit_mut::visit_item_mut(self, item);
+        }
+
+        fn visit_impl_item_mut(&mut self, item: &mut ImplItem) {
+            let span = item.span();
+            match item {
+                ImplItem::Const(item) => self.mark(&mut item.attrs, span),
+                ImplItem::Method(item) => self.mark(&mut item.attrs, span),
+                ImplItem::Type(item) => self.mark(&mut item.attrs, span),
+                _ => {}
+            }
+            visit_mut::visit_impl_item_mut(self, item);
+        }
+
+        fn visit_trait_item_mut(&mut self, item: &mut TraitItem) {
+            let span = item.span();
+            match item {
+                TraitItem::Const(item) => self.mark(&mut item.attrs, span),
+                TraitItem::Method(item) => self.mark(&mut item.attrs, span),
+                TraitItem::Type(item) => self.mark(&mut item.attrs, span),
+                _ => {}
+            }
+            visit_mut::visit_trait_item_mut(self, item);
+        }
+
+        fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+            let span = stmt.span();
+            match stmt {
+                Stmt::Local(local) => self.mark(&mut local.attrs, span),
+                Stmt::Semi(expr, _) => {
+                    if let Some(attrs) = statement_expr_attrs_mut(expr) {
+                        self.mark(attrs, span);
+                    }
+                }
+                // 末尾の式に付けると整形結果が変わってしまうので付けない
+                Stmt::Expr(_) | Stmt::Item(_) => {}
+            }
+            visit_mut::visit_stmt_mut(self, stmt);
+        }
+    }
+
+    let mut marker = Marker {
+        path: display_path(path),
+    };
+    marker.visit_file_mut(file);
+}
+
+/// `path` から展開されたモジュールの境界に目印を付ける。
+pub fn mark_module(attrs: &mut Vec<Attribute>, path: &Path) {
+    let marker = format!("{MODULE_MARKER}{}", display_path(path));
+    attrs.insert(0, parse_quote!(#[doc = #marker]));
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn item_attrs_mut(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    Some(match item {
+        Item::Const(item) => &mut item.attrs,
+        Item::Enum(item) => &mut item.attrs,
+        Item::Fn(item) => &mut item.attrs,
+        Item::Impl(item) => &mut item.attrs,
+        Item::Macro(item) => &mut item.attrs,
+        Item::Mod(item) => &mut item.attrs,
+        Item::Static(item) => &mut item.attrs,
+        Item::Struct(item) => &mut item.attrs,
+        Item::Trait(item) => &mut item.attrs,
+        Item::Type(item) => &mut item.attrs,
+        Item::Union(item) => &mut item.attrs,
+        Item::Use(item) => &mut item.attrs,
+        _ => return None,
+    })
+}
+
+/// 属性を付けても文として解釈が変わらない式の属性
+fn statement_expr_attrs_mut(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    Some(match expr {
+        Expr::Assign(expr) => &mut expr.attrs,
+        Expr::AssignOp(expr) => &mut expr.attrs,
+        Expr::Break(expr) => &mut expr.attrs,
+        Expr::Call(expr) => &mut expr.attrs,
+        Expr::Continue(expr) => &mut expr.attrs,
+        Expr::ForLoop(expr) => &mut expr.attrs,
+        Expr::If(expr) => &mut expr.attrs,
+        Expr::Loop(expr) => &mut expr.attrs,
+        Expr::Macro(expr) => &mut expr.attrs,
+        Expr::Match(expr) => &mut expr.attrs,
+        Expr::MethodCall(expr) => &mut expr.attrs,
+        Expr::Return(expr) => &mut expr.attrs,
+        Expr::While(expr) => &mut expr.attrs,
+        _ => return None,
+    })
+}
+
+/// 整形済みのソースコード中の目印ごとに、目印の付いたアイテムや文の最後の行を求める。キーは目印の行と
+/// 目印が表す位置の組で、行は 1-indexed。パースできなければ空になる。
+fn marked_node_ends(formatted: &str) -> HashMap<(usize, String), usize> {
+    #[derive(Default)]
+    struct Collector {
+        ends: HashMap<(usize, String), usize>,
+    }
+
+    impl Collector {
+        /// `node` の先頭に並ぶ属性のうち、目印の位置を記録する。
+        fn record(&mut self, node: &impl ToTokens) {
+            let end = node.span().end().line;
+            let mut tokens = node.to_token_stream().into_iter();
+            while let (Some(TokenTree::Punct(pound)), Some(TokenTree::Group(group))) =
+                (tokens.next(), tokens.next())
+            {
+                if pound.as_char() != '#' {
+                    break;
+                }
+                if let Some(marker) = source_marker(group.stream()) {
+                    self.ends.insert((group.span().start().line, marker), end);
+                }
+            }
+        }
+    }
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_item(&mut self, item: &'ast Item) {
+            self.record(item);
+            visit::visit_item(self, item);
+        }
+
+        fn visit_impl_item(&mut self, item: &'ast ImplItem) {
+            self.record(item);
+            visit::visit_impl_item(self, item);
+        }
+
+        fn visit_trait_item(&mut self, item: &'ast TraitItem) {
+            self.record(item);
+            visit::visit_trait_item(self, item);
+        }
+
+        fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+            self.record(stmt);
+            visit::visit_stmt(self, stmt);
+        }
+    }
+
+    let Ok(file) = syn::parse_file(formatted) else {
+        return HashMap::new();
+    };
+    let mut collector = Collector::default();
+    collector.visit_file(&file);
+
+    collector.ends
+}
+
+/// `#[doc = "@@src ..."]` の括弧の中身であれば、目印が表す位置を返す。
+fn source_marker(tokens: TokenStream) -> Option<String> {
+    let Ok(Meta::NameValue(meta)) = syn::parse2::<Meta>(tokens) else {
+        return None;
+    };
+    let Lit::Str(lit) = meta.lit else {
+        return None;
+    };
+
+    lit.value()
+        .strip_prefix(SOURCE_MARKER)
+        .map(|location| location.to_string())
+}
+
+/// バンドル結果の各行に対応する元のソースコードの位置
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// 0-indexed の行ごとの位置
+    lines: Vec<Option<Location>>,
+}
+
+impl SourceMap {
+    /// 整形済みのソースコードから目印を取り除き、目印の位置から各行の元の位置を求める。
+    ///
+    /// 目印のない行は、その行を含む最も内側の目印の付いたアイテムや文の先頭からの行数だけずらした
+    /// 位置とする。元のソースコードも rustfmt で整形されているので、行数が元と同じであればほとんどの
+    /// 場合は正確な位置になる。インデントが深くなって折り返しが変わったアイテムの途中の行や、目印の
+    /// 付いたアイテムや文の外の行は位置がわからないので `None` とする。
+    pub fn resolve_markers(formatted: &str) -> (String, SourceMap) {
+        let ends = marked_node_ends(formatted);
+
+        let mut output = String::new();
+        let mut lines = vec![];
+        // 今の行を含む目印の付いたアイテムや文。外側から順に並ぶ
+        let mut nodes: Vec<MarkedNode> = vec![];
+        for (index, line) in formatted.lines().enumerate() {
+            let line_number = index + 1;
+            let mut module = None;
+            let mut sources = vec![];
+            let rest = MARKER_PATTERN.replace_all(line, |caps: &Captures| {
+                match &caps[1] {
+                    "src" => sources.push(caps[2].to_string()),
+                    _ => module = Some(caps[2].to_string()),
+                }
+                ""
+            });
+
+            // 行頭の目印はインデントの後にあるので、取り除いてもインデントは残る
+            let marked = rest.len() != line.len();
+            // 目印だけの行は取り除くので、アイテムや文は次の行から始まる
+            let start = if marked && rest.trim().is_empty() {
+                line_number + 1
+            } else {
+                line_number
+            };
+            nodes.retain(|node| line_number <= node.end);
+            for source in sources {
+                let Some((location, original_end)) = parse_source_marker(&source) else {
+                    continue;
+                };
+                // 構文木が得られなければ先頭の行だけに対応させる
+                let end = ends.get(&(line_number, source)).copied().unwrap_or(start);
+                nodes.push(MarkedNode {
+                    same_lines: end.checked_sub(start) == original_end.checked_sub(location.line),
+                    location,
+                    output_line: lines.len(),
+                    end,
+                });
+            }
+
+            if let Some(module) = module.filter(|_| rest.trim().is_empty()) {
+                output += &format!("{rest}// from {module}\n");
+                lines.push(None);
+                continue;
+            }
+            if marked && rest.trim().is_empty() {
+                // 目印だけの行は取り除く
+                continue;
+            }
+
+            output += &rest;
+            output.push('\n');
+            lines.push(nodes.last().and_then(|node| node.locate(lines.len())));
+        }
+
+        (output, SourceMap { lines })
+    }
+
+    /// 1-indexed の行に対応する元の位置を返す。
+    pub fn lookup(&self, line: usize) -> Option<&Location> {
+        self.lines.get(line.checked_sub(1)?)?.as_ref()
+    }
+
+    /// ジャッジのエラー出力やバックトレースに含まれる `main.rs` の位置を元の位置に置き換える。
+    pub fn unmap_text(&self, text: &str) -> String {
+        static LOCATION_PATTERN: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?:[\w.\-]+/)*[Mm]ain\.rs:(\d+)(?::\d+)?").unwrap());
+
+        LOCATION_PATTERN
+            .replace_all(text, |caps: &Captures| {
+                let location = caps[1].parse().ok().and_then(|line| self.lookup(line));
+                match location {
+                    Some(location) => location.to_string(),
+                    None => caps[0].to_string(),
+                }
+            })
+            .to_string()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut content = String::new();
+        for (index, location) in self.lines.iter().enumerate() {
+            if let Some(location) = location {
+                content += &format!("{}\t{location}\n", index + 1);
+            }
+        }
+
+        // `target` はまだビルドしていなければ存在しない
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to create directory `{}`", dir.display()))?;
+        }
+        fs::write(path, content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write source map to `{}`", path.display()))
+    }
+
+    pub fn read(path: &Path) -> Result<SourceMap> {
+        let content = fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read source map from `{}`", path.display()))?;
+
+        let mut lines = vec![];
+        for entry in content.lines() {
+            let (line, location) = entry
+                .split_once('\t')
+                .and_then(|(line, location)| Some((line.parse::<usize>().ok()?, location)))
+                .and_then(|(line, location)| Some((line, Location::parse(location)?)))
+                .ok_or_else(|| miette!("malformed source map entry: {entry}"))?;
+            if lines.len() < line {
+                lines.resize(line, None);
+            }
+            lines[line - 1] = Some(location);
+        }
+
+        Ok(SourceMap { lines })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(path: &str, line: usize) -> Location {
+        Location {
+            path: path.to_string(),
+            line,
+        }
+    }
+
+    #[test]
+    fn test_mark_items() {
+        let mut file: File =
+            syn::parse_str("fn f() {\n    let x = 1;\n\n    g(x);\n    x\n}\n").unwrap();
+        mark_items(&mut file, Path::new("library/src/a.rs"));
+
+        let Item::Fn(f) = &file.items[0] else {
+            panic!("not a function");
+        };
+        let markers = |attrs: &[Attribute]| {
+            attrs
+                .iter()
+                .map(|attr| attr.tokens.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(markers(&f.attrs), vec![r#"= "@@src library/src/a.rs:1:6""#]);
+        let Stmt::Local(local) = &f.block.stmts[0] else {
+            panic!("not a let statement");
+        };
+        assert_eq!(
+            markers(&local.attrs),
+            vec![r#"= "@@src library/src/a.rs:2:2""#]
+        );
+        let Stmt::Semi(Expr::Call(call), _) = &f.block.stmts[1] else {
+            panic!("not a call statement");
+        };
+        assert_eq!(
+            markers(&call.attrs),
+            vec![r#"= "@@src library/src/a.rs:4:4""#]
+        );
+    }
+
+    #[test]
+    fn test_resolve_markers() {
+        let formatted = [
+            r#"#[doc = "@@from library/src/lib.rs"]"#,
+            "mod library {",
+            r#"    #[doc = "@@src library/src/lib.rs:10:13"]"#,
+            "    pub fn f() {",
+            r#"        #[doc = "@@src library/src/lib.rs:11:11"] g();"#,
+            "        h()",
+            "    }",
+            r#"    #[doc = "@@src library/src/lib.rs:15:15"]"#,
+            "    pub fn long(",
+            "        x: i32,",
+            "    ) {",
+            "    }",
+            "}",
+        ]
+        .join("\n");
+        let (output, source_map) = SourceMap::resolve_markers(&formatted);
+
+        assert_eq!(
+            output,
+            "// from library/src/lib.rs\nmod library {\n    pub fn f() {\n        g();\n        h()\n    }\n    \
+             pub fn long(\n        x: i32,\n    ) {\n    }\n}\n"
+        );
+        // モジュールは目印の付いたアイテムの外にある
+        assert_eq!(source_map.lookup(1), None);
+        assert_eq!(source_map.lookup(2), None);
+        assert_eq!(
+            source_map.lookup(3),
+            Some(&location("library/src/lib.rs", 10))
+        );
+        assert_eq!(
+            source_map.lookup(4),
+            Some(&location("library/src/lib.rs", 11))
+        );
+        // 文が終われば、それを含む関数の先頭からの行数でずらす
+        assert_eq!(
+            source_map.lookup(5),
+            Some(&location("library/src/lib.rs", 12))
+        );
+        assert_eq!(
+            source_map.lookup(6),
+            Some(&location("library/src/lib.rs", 13))
+        );
+        // 折り返しが元と変わったアイテムは先頭の行しかわからない
+        assert_eq!(
+            source_map.lookup(7),
+            Some(&location("library/src/lib.rs", 15))
+        );
+        assert_eq!(source_map.lookup(8), None);
+        assert_eq!(source_map.lookup(10), None);
+        assert_eq!(source_map.lookup(11), None);
+    }
+
+    #[test]
+    fn test_source_map_round_trip() {
+        let (_, source_map) = SourceMap::resolve_markers(
+            "fn main() {}\n#[doc = \"@@src library/src/bs.rs:7:8\"]\nfn f() {\n}\n",
+        );
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("bundle.map");
+        source_map.write(&path).unwrap();
+
+        assert_eq!(SourceMap::read(&path).unwrap(), source_map);
+        assert_eq!(
+            source_map.unmap_text(
+                "thread 'main' panicked at src/main.rs:3:5:\n\
+                 at /rustc/library/core/src/panicking.rs:75:14\n\
+                 at ./Main.rs:1:1"
+            ),
+            "thread 'main' panicked at library/src/bs.rs:8:\n\
+             at /rustc/library/core/src/panicking.rs:75:14\n\
+             at ./Main.rs:1:1"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    visit::{self, Visit},
+    Expr, ImplItem, Item, ItemImpl, Lit, Stmt, Type,
+};
+
+/// Root of the `solutions` crate.
+pub const SOLUTIONS_SRC_DIR: &str = "solutions/src";
+
+/// Source directories of the workspace crates which solutions are built with.
+pub const DEPENDENCY_DIRS: &[&str] = &["library", "referee", "io", "types"];
+
+/// A top-level module of the `solutions` crate which implements a `Solution`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionModule {
+    /// Identifier of the module, such as `naive`.
+    pub ident: String,
+    /// Every source file of the module, sorted.
+    pub files: Vec<PathBuf>,
+    /// Identifier of the type implementing the solution, such as `NaiveSolution`.
+    pub type_ident: String,
+}
+
+/// Finds the module of each solution, keyed by the name returned by `Solution::name()`.
+pub fn find_solution_modules() -> Result<HashMap<String, SolutionModule>> {
+    let src_dir = Path::new(SOLUTIONS_SRC_DIR);
+    let lib_path = src_dir.join("lib.rs");
+    let lib: syn::File = parse_file(&lib_path)?;
+
+    let mut modules = HashMap::new();
+    for item in &lib.items {
+        let Item::Mod(module) = item else {
+            continue;
+        };
+        if module.content.is_some() {
+            continue;
+        }
+
+        let ident = module.ident.to_string();
+        let mut files = vec![];
+        let file_path = src_dir.join(format!("{ident}.rs"));
+        if file_path.exists() {
+            files.push(file_path);
+        }
+        let dir_path = src_dir.join(&ident);
+        if dir_path.is_dir() {
+            files.extend(rust_files_in(&dir_path)?);
+        }
+        files.sort();
+
+        let mut solutions = vec![];
+        for file in &files {
+            solutions.extend(solutions_in(&parse_file(file)?));
+        }
+        for (name, type_ident) in solutions {
+            let module = SolutionModule {
+                ident: ident.clone(),
+                files: files.clone(),
+                type_ident,
+            };
+            modules.insert(name, module);
+        }
+    }
+
+    Ok(modules)
+}
+
+/// Lists `.rs` files under `dir` recursively, sorted.
+pub fn rust_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let entries = fs::read_dir(dir)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read entry in {}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            files.extend(rust_files_in(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn parse_file(path: &Path) -> Result<syn::File> {
+    let source = fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    syn::parse_file(&source)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse {}", path.display()))
+}
+
+/// Collects string literals returned by `fn name()` in `impl Solution for ...` blocks, with the
+/// identifier of the implementing type.
+fn solutions_in(file: &syn::File) -> Vec<(String, String)> {
+    struct Visitor(Vec<(String, String)>);
+
+    impl<'ast> Visit<'ast> for Visitor {
+        fn visit_item_impl(&mut self, i: &'ast ItemImpl) {
+            let is_solution_impl = i.trait_.as_ref().is_some_and(|(_, path, _)| {
+                path.segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Solution")
+            });
+            let type_ident = match &*i.self_ty {
+                Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+                _ => None,
+            };
+            if let Some(type_ident) = type_ident.filter(|_| is_solution_impl) {
+                self.0.extend(
+                    i.items
+                        .iter()
+                        .filter_map(name_literal)
+                        .map(|name| (name, type_ident.clone())),
+                );
+            }
+
+            visit::visit_item_impl(self, i);
+        }
+    }
+
+    let mut visitor = Visitor(vec![]);
+    visitor.visit_file(file);
+
+    visitor.0
+}
+
+fn name_literal(item: &ImplItem) -> Option<String> {
+    let ImplItem::Method(method) = item else {
+        return None;
+    };
+    if method.sig.ident != "name" {
+        return None;
+    }
+
+    let expr = match &*method.block.stmts {
+        [Stmt::Expr(expr)] => expr,
+        _ => return None,
+    };
+    let Expr::Lit(lit) = expr else {
+        return None;
+    };
+    let Lit::Str(lit) = &lit.lit else {
+        return None;
+    };
+
+    Some(lit.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions_in() {
+        let file = syn::parse_file(
+            r#"
+            impl Solution for NaiveSolution {
+                fn name() -> &'static str {
+                    "naive"
+                }
+
+                fn think(&mut self) {}
+            }
+
+            impl Other for NaiveSolution {
+                fn name() -> &'static str {
+                    "other"
+                }
+            }
+
+            mod inner {
+                impl crate::Solution for Inner {
+                    fn name() -> &'static str { "inner" }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            solutions_in(&file),
+            vec![
+                ("naive".to_string(), "NaiveSolution".to_string()),
+                ("inner".to_string(), "Inner".to_string())
+            ]
+        );
+    }
+}
@@ -24,9 +24,9 @@ impl Table {
             assert_eq!(row.len(), column_count, "Row has different column count");
         }
 
-        assert_eq!(
-            self.footer.len(),
-            column_count,
+        // An empty footer is not printed at all.
+        assert!(
+            self.footer.is_empty() || self.footer.len() == column_count,
             "Footer has different column count"
         );
     }
@@ -41,8 +41,10 @@ impl Table {
         for row in &self.body {
             self.print_row(row, &column_content_widths);
         }
-        self.print_separator(&column_content_widths);
-        self.print_row(&self.footer, &column_content_widths);
+        if !self.footer.is_empty() {
+            self.print_separator(&column_content_widths);
+            self.print_row(&self.footer, &column_content_widths);
+        }
     }
 
     fn print_row(&self, row: &[TableCell], column_content_widths: &[usize]) {
@@ -78,7 +80,7 @@ impl Table {
             // Body
             self.body.iter().map(|row| &row[column_index]),
             // Footer
-            Some(&self.footer[column_index]),
+            self.footer.get(column_index),
         )
         .map(|cell| UnicodeWidthStr::width(&*cell.content))
         .max()
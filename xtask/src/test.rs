@@ -2,6 +2,6 @@ use miette::Result;
 
 pub mod ahc;
 
-pub fn main(_args: &[String]) -> Result<()> {
-    ahc::main()
+pub fn main(args: &[String]) -> Result<()> {
+    ahc::main(args)
 }
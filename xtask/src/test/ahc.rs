@@ -1,24 +1,76 @@
 use io::{source::Source, traits::ReadInput};
-use itertools::Itertools;
-use miette::{bail, IntoDiagnostic, Result, WrapErr};
-use once_cell::sync::Lazy;
+use itertools::{izip, Itertools};
+use library::util::metric;
+use miette::{bail, ensure, IntoDiagnostic, Result, WrapErr};
+use once_cell::{sync::Lazy, unsync::OnceCell};
 use rayon::prelude::*;
-use referee::InitInput;
+use referee::{
+    validation::validate_output, ComputeScore, CreateJudge, InitInput, InteractiveJudge, Output,
+    ScoreError, TurnInput, TIME_LIMIT,
+};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use solutions::get_solution_names;
 use std::path::{Path, PathBuf};
-use std::{collections::HashMap, io::BufReader};
-use std::{fs, hash::Hash};
 use std::{
-    fs::File,
-    process::{Command, Stdio},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::BufReader,
+};
+use std::{fs, hash::Hash, mem};
+use std::{fs::File, process::Command};
+use std::{
+    io::Write,
+    time::{Duration, Instant},
 };
-use std::{io::Write, time::Instant};
 
 use crate::table::{Alignment, Table, TableCell};
 
-static ABSOLUTE_BETTER: AbsoluteBetterIs = AbsoluteBetterIs::Maximum;
+use self::{
+    binary::FrozenBinary,
+    config::Config,
+    group::{group_values, DEFAULT_BUCKETS},
+    history::{History, Snapshot},
+    in_process::run_in_process,
+    interactive::{run_interactive, InteractiveOutput},
+    options::Options,
+    process::{run_with_timeout, ProcessOutput},
+    progress::Progress,
+    scoring::Scoring,
+    source_hash::compute_source_hashes,
+    stats::{bootstrap_mean_ci, sign_test_p_value, Summary},
+    timing::{JudgeLimits, TimingStatus},
+};
+
+mod binary;
+mod config;
+mod export;
+mod group;
+mod history;
+mod in_process;
+mod interactive;
+#[cfg(target_os = "linux")]
+pub mod measure;
+mod options;
+mod process;
+mod progress;
+mod scoring;
+mod source_hash;
+mod stats;
+mod timing;
+
+/// Wall-clock limit for each case unless `--timeout` is specified. Solutions are expected to stop
+/// by themselves at `TIME_LIMIT`, so this leaves room for the slowdown by parallel runs.
+const DEFAULT_TIMEOUT: Duration = TIME_LIMIT.saturating_mul(2);
+
+/// Number of the last stderr lines recorded for failed cases.
+const STDERR_TAIL_LINES: usize = 5;
+
+/// Number of failed cases shown in detail below the table.
+const MAX_FAILURE_DETAILS: usize = 10;
+
+/// Number of the slowest seeds of the primary solution listed below the table.
+const SLOWEST_SEEDS: usize = 5;
 
 static SOLUTIONS: Lazy<Vec<Solution>> = Lazy::new(|| {
     get_solution_names()
@@ -27,8 +79,6 @@ static SOLUTIONS: Lazy<Vec<Solution>> = Lazy::new(|| {
         .collect_vec()
 });
 
-static PRIMARY_SOLUTION: Lazy<Solution> = Lazy::new(|| SOLUTIONS.last().cloned().unwrap());
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Cache {
     cache_path: PathBuf,
@@ -38,7 +88,15 @@ struct Cache {
 
 impl Cache {
     pub fn load_or_new(cache_path: &Path) -> Result<Self> {
-        Self::load(cache_path).or_else(|_| Ok(Self::new(cache_path.to_owned())))
+        if !cache_path.exists() {
+            return Ok(Self::new(cache_path.to_owned()));
+        }
+
+        // The format may have changed since the cache was written. Its results are lost, so say so.
+        Self::load(cache_path).or_else(|e| {
+            eprintln!("warning: discarding cached results: {e:?}");
+            Ok(Self::new(cache_path.to_owned()))
+        })
     }
 
     pub fn new(cache_path: PathBuf) -> Self {
@@ -76,13 +134,116 @@ impl Drop for Cache {
     }
 }
 
-pub fn main() -> Result<()> {
-    let tester = Tester::detect().wrap_err("failed to detect testing tools")?;
+pub fn main(args: &[String]) -> Result<()> {
+    let options = Options::parse(args).wrap_err("failed to parse options")?;
+    if let Some(jobs) = options.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .into_diagnostic()
+            .wrap_err("failed to configure thread pool")?;
+    }
+
+    if let Some(key) = &options.group_by {
+        let keys = InitInput::description_keys();
+        ensure!(
+            keys.contains(&&**key),
+            "unknown parameter to group by: {} (available: {})",
+            key,
+            keys.join(", ")
+        );
+    }
+
+    // Fail before running the tests rather than after.
+    if let Some(export_path) = &options.export {
+        export::Format::of(export_path)?;
+    }
+
+    let solutions = select_solutions(&options).wrap_err("failed to select solutions")?;
+    let primary_solution = solutions.last().cloned().expect("no solution selected");
+    let source_hashes =
+        compute_source_hashes(&solutions).wrap_err("failed to compute source hashes")?;
+
+    let config = Config::load_or_default(Path::new(Config::FILE_NAME))
+        .wrap_err_with(|| format!("failed to load {}", Config::FILE_NAME))?;
+    let score_regex = config.compile_score_regex()?;
+
+    let tester = Tester::detect(&config).wrap_err("failed to detect testing tools")?;
+    if options.in_process {
+        ensure!(
+            tester.bin_tester.is_none(),
+            "--in-process does not support interactive problems"
+        );
+        eprintln!(
+            "note: results of --in-process are neither cached nor archived, since they are not \
+             comparable with those of the binary"
+        );
+        if cfg!(debug_assertions) {
+            eprintln!(
+                "warning: xtask is built without optimizations, and so are the solutions run \
+                 in-process; run `cargo run --release --package xtask -- test --in-process` \
+                 for realistic timings"
+            );
+        }
+    }
     let cache_path = tester.testing_dir.join("cache.json");
     let mut cache = Cache::load_or_new(&cache_path)?;
 
-    for solution in &*SOLUTIONS {
-        if solution != &*PRIMARY_SOLUTION && cache.results.contains_key(solution) {
+    // Load the snapshot before archiving this run so that `latest` means the previous run.
+    let history = History::new(&tester.testing_dir);
+    let comparison = options
+        .compare
+        .as_deref()
+        .map(|query| history.load(query))
+        .transpose()
+        .wrap_err("failed to load snapshot to compare with")?;
+    if let Some(snapshot) = &comparison {
+        if snapshot.solution != primary_solution {
+            eprintln!(
+                "warning: snapshot {} is a run of {}, not of the primary solution {}",
+                snapshot.name,
+                snapshot.solution.inner(),
+                primary_solution.inner()
+            );
+        }
+    }
+
+    // Built lazily, only when some solution has to be run.
+    let binary = OnceCell::new();
+    // Results of --in-process, which take precedence over the cache.
+    let mut in_process_results: HashMap<Solution, HashMap<Seed, TestCaseResult>> = HashMap::new();
+    let mut seeds = vec![];
+    for solution in &solutions {
+        let env = TestEnvironment::new(
+            &mut cache,
+            tester.clone(),
+            solution.clone(),
+            source_hashes[solution].clone(),
+            score_regex.clone(),
+            &options,
+        )
+        .wrap_err("failed to initialize test environment")?;
+        seeds = env.cases.iter().map(|case| case.seed.clone()).collect_vec();
+
+        // The primary solution is always rerun. Other solutions only run the cases missing from
+        // the cache or whose source has changed, unless `--force` is specified.
+        let cases = if options.force || solution == &primary_solution {
+            env.cases.clone()
+        } else {
+            let cached = cache.results.get(solution);
+            env.cases
+                .iter()
+                .filter(|case| {
+                    let Some(result) = cached.and_then(|results| results.get(&case.seed)) else {
+                        return true;
+                    };
+                    !options.keep_stale && result.is_stale(&source_hashes[solution])
+                })
+                .cloned()
+                .collect_vec()
+        };
+
+        if cases.is_empty() {
             eprintln!(
                 "skipping non-primary and cached solution: {}",
                 solution.inner()
@@ -90,38 +251,134 @@ pub fn main() -> Result<()> {
             continue;
         }
 
+        let binary = if options.in_process {
+            None
+        } else {
+            Some(binary.get_or_try_init(|| FrozenBinary::build(&tester.testing_dir))?)
+        };
         eprintln!("running solution: {}", solution.inner());
-        let env = TestEnvironment::new(&mut cache, tester.clone(), solution.clone())
-            .wrap_err("failed to initialize test environment")?;
-        let seeds = env.seeds.clone();
-        if solution != &*PRIMARY_SOLUTION
-            && cache.results.contains_key(solution)
-            && cache.results[solution].keys().cloned().collect_vec() == seeds
-        {
+        let results = env
+            .run_solution(binary, &cases, cache.results.get(solution))
+            .wrap_err("failed to run solution")?;
+        if options.in_process {
+            in_process_results.insert(solution.clone(), results);
             continue;
         }
-
-        cache.results.insert(
-            solution.clone(),
-            env.run_solution().wrap_err("failed to run solution")?,
-        );
+        if solution == &primary_solution {
+            let name = history
+                .archive(solution, &results)
+                .wrap_err("failed to archive results")?;
+            eprintln!("archived results as {}", name);
+        }
+        cache
+            .results
+            .entry(solution.clone())
+            .or_default()
+            .extend(results);
     }
 
-    TablePrinter::new(cache.results.clone()).print();
+    let results = solutions
+        .iter()
+        .map(|solution| {
+            let results = seeds
+                .iter()
+                .map(|seed| {
+                    let result = in_process_results
+                        .get(solution)
+                        .and_then(|results| results.get(seed))
+                        .unwrap_or_else(|| &cache.results[solution][seed]);
+                    (seed.clone(), result.clone())
+                })
+                .collect();
+            (solution.clone(), results)
+        })
+        .collect();
+    if let Some(export_path) = &options.export {
+        export::write(export_path, &export_records(&results, &solutions))
+            .wrap_err("failed to export results")?;
+        eprintln!("exported results to {}", export_path.display());
+    }
+    TablePrinter::new(
+        results,
+        solutions,
+        primary_solution,
+        source_hashes,
+        comparison,
+        &config,
+        &options,
+    )
+    .print();
 
     Ok(())
 }
 
-#[derive(Debug)]
-// This enum is only used as a constant, so `dead_code` detection is not useful here.
-#[allow(dead_code)]
+fn export_records(
+    results: &HashMap<Solution, HashMap<Seed, TestCaseResult>>,
+    solutions: &[Solution],
+) -> Vec<export::Record> {
+    solutions
+        .iter()
+        .flat_map(|solution| {
+            results[solution]
+                .values()
+                .sorted_by_key(|result| &result.seed)
+                .map(|result| export::Record {
+                    solution: solution.inner().to_string(),
+                    seed: result.seed.inner().to_string(),
+                    parameters: izip!(
+                        InitInput::description_keys(),
+                        result.init_input.description_values()
+                    )
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+                    score: result.score.as_ref().ok().copied(),
+                    outcome: match &result.score {
+                        Ok(_) => export::OUTCOME_OK.to_string(),
+                        Err(failure) => failure.label().to_string(),
+                    },
+                    duration_millis: result.duration_millis,
+                    cpu_time_millis: result.cpu_time_millis,
+                    peak_memory_kib: result.peak_memory_kib,
+                    metrics: result.metrics.clone(),
+                })
+        })
+        .collect()
+}
+
+fn select_solutions(options: &Options) -> Result<Vec<Solution>> {
+    if options.solutions.is_empty() {
+        return Ok(SOLUTIONS.clone());
+    }
+
+    for name in &options.solutions {
+        if !SOLUTIONS.iter().any(|solution| solution.inner() == name) {
+            bail!("unknown solution: {}", name);
+        }
+    }
+
+    // Keep the registration order so that the primary solution is still the last one.
+    Ok(SOLUTIONS
+        .iter()
+        .filter(|solution| {
+            options
+                .solutions
+                .iter()
+                .any(|name| solution.inner() == name)
+        })
+        .cloned()
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
 enum AbsoluteBetterIs {
+    #[serde(rename = "minimize")]
     Minimum,
+    #[serde(rename = "maximize")]
     Maximum,
 }
 
 impl AbsoluteBetterIs {
-    pub fn _is_former<T: Ord>(&self, a: T, b: T) -> bool {
+    pub fn is_former<T: Ord>(&self, a: T, b: T) -> bool {
         match self {
             Self::Minimum => a < b,
             Self::Maximum => a > b,
@@ -162,8 +419,96 @@ struct TestCaseResult {
     seed: Seed,
     in_filename: String,
     init_input: InitInput,
-    score: Result<u64, ()>,
+    score: Result<u64, Failure>,
     duration_millis: i64,
+    /// Source hash of the solution when this result was produced.
+    #[serde(default)]
+    source_hash: Option<String>,
+    /// Hash of the binary which produced this result.
+    #[serde(default)]
+    binary_hash: Option<String>,
+    /// User and system CPU time. Only measured on Linux.
+    #[serde(default)]
+    cpu_time_millis: Option<i64>,
+    /// Peak resident set size. Only measured on Linux.
+    #[serde(default)]
+    peak_memory_kib: Option<u64>,
+    /// Values reported by `library::metric!`.
+    #[serde(default)]
+    metrics: BTreeMap<String, String>,
+}
+
+impl TestCaseResult {
+    /// How close the run was to the judge time limit.
+    fn timing(&self, limits: &JudgeLimits) -> TimingStatus {
+        limits.classify(self.duration_millis, self.cpu_time_millis)
+    }
+
+    /// Whether the solution has changed since this result was produced. Results are never stale
+    /// if the current hash is unknown.
+    fn is_stale(&self, current_source_hash: &Option<String>) -> bool {
+        current_source_hash
+            .as_ref()
+            .is_some_and(|hash| self.source_hash.as_ref() != Some(hash))
+    }
+
+    /// Absolute score, or the label of the failure.
+    fn score_display(&self) -> String {
+        match &self.score {
+            Ok(score) => score.to_string(),
+            Err(failure) => failure.label().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Failure {
+    /// The solution exited abnormally.
+    RuntimeError {
+        exit_status: String,
+        stderr_tail: String,
+    },
+    /// The visualizer reported zero, which usually means a wrong answer.
+    ZeroScore,
+    /// The visualizer output did not contain a score.
+    UnparsableVisualizerOutput,
+    /// The solution did not finish within the timeout and was killed.
+    TimeLimitExceeded,
+    /// The interactive tester exited abnormally.
+    TesterError {
+        exit_status: String,
+        stderr_tail: String,
+    },
+    /// The validator, the native scorer or the local judge rejected the output.
+    WrongAnswer { message: String },
+}
+
+impl Failure {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::RuntimeError { .. } => "RE",
+            Self::ZeroScore => "ZERO",
+            Self::UnparsableVisualizerOutput => "NO SCORE",
+            Self::TimeLimitExceeded => "TLE",
+            Self::TesterError { .. } => "TESTER",
+            Self::WrongAnswer { .. } => "WA",
+        }
+    }
+
+    fn details(&self) -> Option<(&str, &str)> {
+        match self {
+            Self::RuntimeError {
+                exit_status,
+                stderr_tail,
+            }
+            | Self::TesterError {
+                exit_status,
+                stderr_tail,
+            } => Some((exit_status, stderr_tail)),
+            Self::WrongAnswer { message } => Some(("rejected", message)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -201,15 +546,16 @@ struct Tester {
 }
 
 impl Tester {
-    pub fn detect() -> Result<Self> {
-        let testing_dir = PathBuf::from("testing");
+    pub fn detect(config: &Config) -> Result<Self> {
+        let testing_dir = config.testing_dir.clone();
         let testing_tools_dir = testing_dir.join("tools");
 
         let testing_binaries_dir =
             Self::ensure_built(&testing_tools_dir).wrap_err("failed to locate testing tools")?;
-        let bin_gen = testing_binaries_dir.join("gen");
-        let bin_vis = testing_binaries_dir.join("vis");
-        let bin_tester = Some(testing_binaries_dir.join("tester")).filter(|p| p.exists());
+        let bin_gen = testing_binaries_dir.join(&config.tools.gen);
+        let bin_vis = testing_binaries_dir.join(&config.tools.vis);
+        let bin_tester =
+            Some(testing_binaries_dir.join(&config.tools.tester)).filter(|p| p.exists());
 
         Ok(Self {
             testing_dir,
@@ -237,22 +583,46 @@ impl Tester {
     }
 }
 
+/// Outcome of a case run against the local judge.
+#[derive(Debug)]
+struct JudgeResult {
+    /// Score given by the judge, or why the interaction failed.
+    score: Result<i64, String>,
+    /// Whether the solution was killed because the judge rejected its output.
+    killed_on_rejection: bool,
+    /// Summary of the time taken by the turns.
+    turn_log: String,
+}
+
+#[derive(Debug, Clone)]
+struct TestCase {
+    seed: Seed,
+    in_filename: String,
+}
+
 #[derive(Debug)]
 struct TestEnvironment {
     target_solution: Solution,
     tester: Tester,
     in_dir: PathBuf,
     out_dir: PathBuf,
-    seeds: Vec<Seed>,
-    in_filenames: Vec<String>,
+    cases: Vec<TestCase>,
+    timeout: Duration,
+    source_hash: Option<String>,
+    score_regex: Regex,
 }
 
 impl TestEnvironment {
-    fn new(cache: &mut Cache, tester: Tester, target_solution: Solution) -> Result<Self> {
-        let in_dir = Path::new("testing").join("in");
-        let out_dir = Path::new("testing")
-            .join("out")
-            .join(target_solution.inner());
+    fn new(
+        cache: &mut Cache,
+        tester: Tester,
+        target_solution: Solution,
+        source_hash: Option<String>,
+        score_regex: Regex,
+        options: &Options,
+    ) -> Result<Self> {
+        let in_dir = tester.testing_dir.join("in");
+        let out_dir = tester.testing_dir.join("out").join(target_solution.inner());
 
         let seeds =
             Self::ensure_seeds(cache, &tester, &in_dir).wrap_err("failed to ensure seeds")?;
@@ -271,13 +641,45 @@ impl TestEnvironment {
             .filter(|name| name.ends_with(".txt"))
             .collect();
 
+        let mut cases = vec![];
+        for in_filename in in_filenames {
+            let number: usize = in_filename
+                .trim_end_matches(".txt")
+                .parse()
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("failed to parse input file name index: {in_filename}")
+                })?;
+            if !options.is_seed_selected(number) {
+                continue;
+            }
+
+            let seed = seeds
+                .get(number)
+                .ok_or_else(|| miette::miette!("no seed for input file: {in_filename}"))?;
+            cases.push((
+                number,
+                TestCase {
+                    seed: seed.clone(),
+                    in_filename,
+                },
+            ));
+        }
+        let cases = cases
+            .into_iter()
+            .sorted_by_key(|(number, _)| *number)
+            .map(|(_, case)| case)
+            .collect();
+
         Ok(Self {
             target_solution,
             tester,
             in_dir,
             out_dir,
-            seeds,
-            in_filenames,
+            cases,
+            timeout: options.timeout.unwrap_or(DEFAULT_TIMEOUT),
+            source_hash,
+            score_regex,
         })
     }
 
@@ -325,11 +727,8 @@ impl TestEnvironment {
     }
 
     fn ensure_out_dir(&self) -> Result<()> {
-        if self.out_dir.exists() {
-            fs::remove_dir_all(&self.out_dir)
-                .into_diagnostic()
-                .wrap_err("failed to remove existing output directory")?;
-        }
+        // Outputs of the cases not selected in this run are kept, since their results remain in
+        // the cache.
         fs::create_dir_all(&self.out_dir)
             .into_diagnostic()
             .wrap_err("failed to create output directory")?;
@@ -337,61 +736,45 @@ impl TestEnvironment {
         Ok(())
     }
 
-    fn run_solution(&self) -> Result<HashMap<Seed, TestCaseResult>> {
+    /// Runs `cases` with `binary`, or in this process if `None`. `previous` are the cached results
+    /// of the solution, used to show how the current run compares.
+    fn run_solution(
+        &self,
+        binary: Option<&FrozenBinary>,
+        cases: &[TestCase],
+        previous: Option<&HashMap<Seed, TestCaseResult>>,
+    ) -> Result<HashMap<Seed, TestCaseResult>> {
         self.ensure_out_dir()
             .wrap_err("failed to ensure output directory")?;
 
-        eprintln!("building binary");
-        let out = Command::new("cargo")
-            .args(["build", "--release"])
-            .spawn()
-            .into_diagnostic()
-            .wrap_err("failed to execute solution binary")?
-            .wait()
-            .into_diagnostic()
-            .wrap_err("failed to wait solution binary")?;
-
-        if !out.success() {
-            bail!("failed to build solution binary");
-        }
-
-        let results: HashMap<Seed, TestCaseResult> = (0..self.in_filenames.len())
-            .into_par_iter()
-            .map(|case_index| {
-                self.test_for_input_index(case_index)
-                    .map(|r| (r.seed.clone(), r))
-                    .with_context(|| {
-                        format!("failed to run test for {}", self.in_filenames[case_index])
-                    })
+        let progress = Progress::new(self.target_solution.inner(), cases.len());
+        let results: Result<HashMap<Seed, TestCaseResult>> = cases
+            .par_iter()
+            .map(|case| {
+                let result = self
+                    .test_for_case(binary, case)
+                    .with_context(|| format!("failed to run test for {}", case.in_filename))?;
+                progress.record(&result, previous.and_then(|p| p.get(&case.seed)));
+                Ok((result.seed.clone(), result))
             })
-            .collect::<Result<_>>()
-            .wrap_err("some test cases critically failed")?;
+            .collect();
+        progress.finish();
 
-        Ok(results)
+        results.wrap_err("some test cases critically failed")
     }
 
-    fn test_for_input_index(&self, case_index: usize) -> Result<TestCaseResult> {
-        let in_filename = &self.in_filenames[case_index];
-        let total_cases = self.in_filenames.len();
-
-        println!(
-            "testing {} by {}... ({}/{})",
-            in_filename,
-            self.target_solution.inner(),
-            case_index + 1,
-            total_cases
-        );
-
-        let number: usize = in_filename
-            .trim_end_matches(".txt")
-            .parse()
-            .into_diagnostic()
-            .wrap_err("failed to parse input file name index")?;
-        let seed = &self.seeds[number];
+    /// Runs a case with `binary`, or in this process if `None`.
+    fn test_for_case(
+        &self,
+        binary: Option<&FrozenBinary>,
+        case: &TestCase,
+    ) -> Result<TestCaseResult> {
+        let TestCase { seed, in_filename } = case;
 
         let in_file_path = self.in_dir.join(in_filename);
         let out_file_path = self.out_dir.join(in_filename);
         let err_file_path = self.out_dir.join(format!("{}.stderr", in_filename));
+        let transcript_file_path = self.out_dir.join(format!("{}.transcript", in_filename));
 
         let in_file_content = fs::read_to_string(&in_file_path)
             .into_diagnostic()
@@ -399,44 +782,40 @@ impl TestEnvironment {
         let init_input =
             InitInput::read_from(&mut Source::new(BufReader::new(in_file_content.as_bytes())));
 
-        let start_time = Instant::now();
-
-        let mut main_process = if let Some(bin_tester) = &self.tester.bin_tester {
-            // Interactive
-            Command::new(bin_tester)
-                .arg(Path::new("target").join("release").join("main"))
-                .arg(self.target_solution.inner())
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .into_diagnostic()
-                .wrap_err("failed to spawn tester (interactive)")?
+        // The local judge is used only when there is no official tester.
+        let judge = if self.tester.bin_tester.is_none() {
+            init_input.create_judge()
         } else {
-            // Non-interactive
-            Command::new(Path::new("target").join("release").join("main"))
-                .env("RUST_BACKTRACE", "1")
-                .arg(self.target_solution.inner())
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .into_diagnostic()
-                .wrap_err("failed to spawn solution binary (non-interactive)")?
+            None
         };
+        let interactive = self.tester.bin_tester.is_some() || judge.is_some();
 
-        if let Some(mut stdin) = main_process.stdin.take() {
-            stdin
-                .write_all(in_file_content.as_bytes())
-                .into_diagnostic()
-                .wrap_err("failed to write to stdin")?;
-        }
+        let start_time = Instant::now();
 
-        let output = main_process
-            .wait_with_output()
-            .into_diagnostic()
-            .wrap_err("failed to wait for main process to finish")?;
+        let (output, judged) = match binary {
+            Some(binary) => self
+                .run_binary(
+                    binary,
+                    &in_file_content,
+                    &init_input,
+                    judge,
+                    &transcript_file_path,
+                )
+                .wrap_err("failed to run main process")?,
+            None if interactive => bail!("--in-process does not support interactive problems"),
+            None => (
+                run_in_process(
+                    self.target_solution.inner(),
+                    in_file_content.as_bytes(),
+                    self.timeout,
+                )?,
+                None,
+            ),
+        };
         let duration_millis = start_time.elapsed().as_millis() as i64;
+        let cpu_time_millis = output.usage.map(|usage| usage.cpu_time.as_millis() as i64);
+        let peak_memory_kib = output.usage.and_then(|usage| usage.peak_rss_kib);
+        let metrics = parse_metrics(&output.stderr);
 
         fs::write(&out_file_path, &output.stdout)
             .into_diagnostic()
@@ -445,28 +824,93 @@ impl TestEnvironment {
             .into_diagnostic()
             .wrap_err("failed to write stderr to file")?;
 
-        let output = Command::new(&self.tester.bin_vis)
-            .args([&in_file_path, &out_file_path])
-            .output()
-            .into_diagnostic()
-            .wrap_err("failed to run visualizer")?;
-
-        let vis_out_output = String::from_utf8_lossy(&output.stdout).into_owned()
-            + &String::from_utf8_lossy(&output.stderr);
-        let re = Regex::new(r"Score = (?<score>\d*)").unwrap();
-        let score = re
-            .captures(&vis_out_output)
-            .and_then(|m| {
-                m.name("score")
-                    .expect("named capture not found")
-                    .as_str()
-                    .parse()
-                    .ok()
+        // The solution is killed once the judge rejects it, in which case its exit status is
+        // meaningless. Otherwise a solution which crashed is a runtime error whatever the judge says.
+        let killed_on_rejection = judged
+            .as_ref()
+            .is_some_and(|judged| judged.killed_on_rejection);
+        let failure = if output.timed_out {
+            Some(Failure::TimeLimitExceeded)
+        } else if !output.status.success() && !killed_on_rejection {
+            let exit_status = output.status.to_string();
+            let stderr_tail = tail_lines(&String::from_utf8_lossy(&output.stderr));
+            Some(if self.tester.bin_tester.is_some() {
+                Failure::TesterError {
+                    exit_status,
+                    stderr_tail,
+                }
+            } else {
+                Failure::RuntimeError {
+                    exit_status,
+                    stderr_tail,
+                }
+            })
+        } else if let Some(JudgeResult {
+            score: Err(message),
+            ..
+        }) = &judged
+        {
+            Some(Failure::WrongAnswer {
+                message: message.clone(),
             })
-            .filter(|&score| score > 0)
-            .ok_or(());
+        } else if interactive {
+            // Outputs of the local judge are validated during the interaction.
+            None
+        } else {
+            self.validate(&init_input, &in_file_content, &output.stdout)
+                .err()
+                .map(|message| Failure::WrongAnswer { message })
+        };
+
+        if let Some(failure) = failure {
+            let message = match &failure {
+                Failure::TimeLimitExceeded => {
+                    format!("Killed after {} ms (timeout)", duration_millis)
+                }
+                Failure::WrongAnswer { message } => format!("Invalid output: {}", message),
+                _ => format!("Exited with {}", output.status),
+            };
+            File::options()
+                .append(true)
+                .open(&err_file_path)
+                .into_diagnostic()
+                .wrap_err("failed to open stderr file")?
+                .write_all(format!("\n{}\n", message).as_bytes())
+                .into_diagnostic()
+                .wrap_err("failed to append failure")?;
+
+            return Ok(TestCaseResult {
+                in_filename: in_filename.clone(),
+                seed: seed.clone(),
+                score: Err(failure),
+                init_input,
+                duration_millis,
+                source_hash: self.source_hash.clone(),
+                binary_hash: binary.map(|binary| binary.hash.clone()),
+                cpu_time_millis,
+                peak_memory_kib,
+                metrics,
+            });
+        }
+
+        let (score, score_log) = if let Some(JudgeResult {
+            score: Ok(score),
+            turn_log,
+            ..
+        }) = judged
+        {
+            let (score, score_log) = Self::accept_score(Ok(score));
+            (score, turn_log + &score_log)
+        } else if let Some(scored) = (!interactive)
+            .then(|| self.score_natively(&init_input, &output.stdout))
+            .flatten()
+        {
+            scored
+        } else {
+            self.score_by_visualizer(&in_file_path, &out_file_path)?
+        };
 
-        // Append visualizer result
+        // Append scoring result
         // To keep output file valid, we need to append the result to the stderr file even though
         // tester does write to stdout.
         File::options()
@@ -474,9 +918,9 @@ impl TestEnvironment {
             .open(&err_file_path)
             .into_diagnostic()
             .wrap_err("failed to open stdout file")?
-            .write_all(vis_out_output.as_bytes())
+            .write_all(score_log.as_bytes())
             .into_diagnostic()
-            .wrap_err("failed to append visualizer stdout")?;
+            .wrap_err("failed to append scoring result")?;
 
         // Append duration
         File::options()
@@ -484,7 +928,15 @@ impl TestEnvironment {
             .open(&err_file_path)
             .into_diagnostic()
             .wrap_err("failed to open stdout file")?
-            .write_all(format!("\nDuration: {} ms\n", duration_millis).as_bytes())
+            .write_all(
+                format!(
+                    "\nDuration: {} ms\nCPU time: {}\nPeak memory: {}\n",
+                    duration_millis,
+                    format_cpu_time(cpu_time_millis),
+                    format_memory(peak_memory_kib)
+                )
+                .as_bytes(),
+            )
             .into_diagnostic()
             .wrap_err("failed to append duration")?;
 
@@ -494,23 +946,246 @@ impl TestEnvironment {
             score,
             init_input,
             duration_millis,
+            source_hash: self.source_hash.clone(),
+            binary_hash: binary.map(|binary| binary.hash.clone()),
+            cpu_time_millis,
+            peak_memory_kib,
+            metrics,
         })
     }
+
+    /// Runs the binary against the tester or `judge` if any.
+    fn run_binary(
+        &self,
+        binary: &FrozenBinary,
+        in_file_content: &str,
+        init_input: &InitInput,
+        judge: Option<Box<dyn InteractiveJudge>>,
+        transcript_file_path: &Path,
+    ) -> Result<(ProcessOutput, Option<JudgeResult>)> {
+        let mut main_command = if let Some(bin_tester) = &self.tester.bin_tester {
+            // Interactive
+            let mut command = Command::new(bin_tester);
+            command.arg(&binary.path).arg(self.target_solution.inner());
+            command
+        } else {
+            // Non-interactive
+            let mut command = Command::new(&binary.path);
+            command
+                .env("RUST_BACKTRACE", "1")
+                .arg(self.target_solution.inner());
+            command
+        };
+
+        if let Some(judge) = judge {
+            let InteractiveOutput {
+                process,
+                transcript,
+                turn_millis,
+                score,
+                killed_on_rejection,
+            } = run_interactive(&mut main_command, init_input, judge, self.timeout)
+                .wrap_err("failed to interact with the local judge")?;
+            fs::write(transcript_file_path, transcript)
+                .into_diagnostic()
+                .wrap_err("failed to write transcript to file")?;
+            let judged = JudgeResult {
+                score,
+                killed_on_rejection,
+                turn_log: format_turns(&turn_millis),
+            };
+            Ok((process, Some(judged)))
+        } else {
+            let output = run_with_timeout(
+                &mut main_command,
+                in_file_content.as_bytes().to_vec(),
+                self.timeout,
+            )?;
+            Ok((output, None))
+        }
+    }
+
+    /// Validates the output of a non-interactive solution, returning the first error. The rules are
+    /// checked only if `Validate` is implemented.
+    fn validate(
+        &self,
+        init_input: &InitInput,
+        in_file_content: &str,
+        stdout: &[u8],
+    ) -> Result<(), String> {
+        // The inputs of the turns follow the initial input. The first turn always exists, as the
+        // solution reads it even if its input is empty, and the later ones end at EOF so that extra
+        // outputs are reported.
+        let mut source = Source::new(BufReader::new(in_file_content.as_bytes()));
+        InitInput::read_from(&mut source);
+        let mut first = true;
+        let turns = std::iter::from_fn(|| {
+            let has_turn = mem::take(&mut first) || !source.is_empty();
+            has_turn.then(|| TurnInput::read_from(&mut source))
+        });
+        validate_output(init_input, turns, &String::from_utf8_lossy(stdout))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Scores the output of a non-interactive solution by `ComputeScore`, returning the score and a
+    /// log line. `None` if the native scorer is not implemented or the output cannot be parsed, which
+    /// is left to the visualizer.
+    fn score_natively(
+        &self,
+        init_input: &InitInput,
+        stdout: &[u8],
+    ) -> Option<(Result<u64, Failure>, String)> {
+        let output = String::from_utf8_lossy(stdout).parse::<Output>().ok()?;
+
+        init_input.compute_score(&output).map(Self::accept_score)
+    }
+
+    /// Checks the score given by `referee`, returning the score and a log line.
+    fn accept_score(score: Result<i64, ScoreError>) -> (Result<u64, Failure>, String) {
+        let score = score.and_then(|score| {
+            u64::try_from(score)
+                .map_err(|_| ScoreError::Invalid(format!("negative score: {score}")))
+        });
+
+        match score {
+            Ok(0) => (Err(Failure::ZeroScore), "Score = 0\n".to_string()),
+            Ok(score) => (Ok(score), format!("Score = {score}\n")),
+            Err(e) => {
+                let log = format!("{e}\n");
+                let message = e.to_string();
+                (Err(Failure::WrongAnswer { message }), log)
+            }
+        }
+    }
+
+    /// Runs the visualizer and extracts the score from its output, returning the score and the
+    /// output.
+    fn score_by_visualizer(
+        &self,
+        in_file_path: &Path,
+        out_file_path: &Path,
+    ) -> Result<(Result<u64, Failure>, String)> {
+        let output = Command::new(&self.tester.bin_vis)
+            .args([in_file_path, out_file_path])
+            .output()
+            .into_diagnostic()
+            .wrap_err("failed to run visualizer")?;
+
+        let vis_out_output = String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr);
+        let score = self
+            .score_regex
+            .captures(&vis_out_output)
+            .and_then(|m| {
+                m.name("score")
+                    .expect("named capture not found")
+                    .as_str()
+                    .parse()
+                    .ok()
+            })
+            .ok_or(Failure::UnparsableVisualizerOutput)
+            .and_then(|score| match score {
+                0 => Err(Failure::ZeroScore),
+                score => Ok(score),
+            });
+
+        Ok((score, vis_out_output))
+    }
+}
+
+/// Keeps the last `STDERR_TAIL_LINES` lines of `stderr` to be stored in the cache. A panic backtrace
+/// is dropped so that the panic message remains.
+fn tail_lines(stderr: &str) -> String {
+    let lines = stderr
+        .lines()
+        .take_while(|line| *line != "stack backtrace:")
+        .collect_vec();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..]
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn format_cpu_time(cpu_time_millis: Option<i64>) -> String {
+    cpu_time_millis.map_or_else(|| "-".to_string(), |millis| format!("{} ms", millis))
+}
+
+fn format_memory(peak_memory_kib: Option<u64>) -> String {
+    peak_memory_kib.map_or_else(
+        || "-".to_string(),
+        |kib| format!("{:.1} MiB", kib as f64 / 1024.0),
+    )
+}
+
+/// Collects the values reported by `library::metric!` from stderr. A later value of the same key
+/// overrides the earlier ones.
+fn parse_metrics(stderr: &[u8]) -> BTreeMap<String, String> {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .filter_map(metric::parse_line)
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Summary of the time taken by each turn of an interaction.
+fn format_turns(turn_millis: &[i64]) -> String {
+    let total: i64 = turn_millis.iter().sum();
+    let max = turn_millis.iter().max().copied().unwrap_or(0);
+    format!(
+        "Turns: {}, total {} ms, max {} ms\n",
+        turn_millis.len(),
+        total,
+        max
+    )
 }
 
 #[derive(Debug)]
 struct TablePrinter {
     _solution_seed_results: HashMap<Solution, HashMap<Seed, TestCaseResult>>,
     seed_solution_results: HashMap<Seed, HashMap<Solution, TestCaseResult>>,
+    solutions: Vec<Solution>,
+    primary_solution: Solution,
+    source_hashes: HashMap<Solution, Option<String>>,
+    comparison: Option<Snapshot>,
+    absolute_better: AbsoluteBetterIs,
+    scoring: Scoring,
+    group_by: Option<String>,
+    buckets: usize,
+    show_cpu: bool,
+    show_memory: bool,
+    show_metrics: bool,
+    judge: JudgeLimits,
 }
 
 impl TablePrinter {
-    fn new(solution_seed_results: HashMap<Solution, HashMap<Seed, TestCaseResult>>) -> Self {
-        let seed_solution_results = Self::transpose_results(&solution_seed_results);
+    fn new(
+        solution_seed_results: HashMap<Solution, HashMap<Seed, TestCaseResult>>,
+        solutions: Vec<Solution>,
+        primary_solution: Solution,
+        source_hashes: HashMap<Solution, Option<String>>,
+        comparison: Option<Snapshot>,
+        config: &Config,
+        options: &Options,
+    ) -> Self {
+        let seed_solution_results =
+            Self::transpose_results(&solution_seed_results, &primary_solution);
 
         Self {
             _solution_seed_results: solution_seed_results,
             seed_solution_results,
+            solutions,
+            primary_solution,
+            source_hashes,
+            comparison,
+            absolute_better: config.objective,
+            scoring: config.scoring,
+            group_by: options.group_by.clone(),
+            buckets: options.buckets.unwrap_or(DEFAULT_BUCKETS),
+            show_cpu: options.show_cpu,
+            show_memory: options.show_memory,
+            show_metrics: options.show_metrics,
+            judge: config.judge,
         }
     }
 
@@ -527,13 +1202,346 @@ impl TablePrinter {
         );
 
         table.print();
+        self.print_legend();
+        self.print_statistics();
+        self.print_timing();
+        if let Some(key) = &self.group_by {
+            self.print_groups(key);
+        }
+        self.print_failures();
+    }
+
+    /// Explains the markers which appear in the table.
+    fn print_legend(&self) {
+        let results = || {
+            self.seed_solution_results
+                .values()
+                .flat_map(|solution_results| solution_results.iter())
+        };
+
+        if results().any(|(solution, result)| result.is_stale(&self.source_hashes[solution])) {
+            println!("* stale: the solution has changed since the result was cached");
+        }
+        let timings = results()
+            .map(|(_, result)| result.timing(&self.judge))
+            .collect::<HashSet<_>>();
+        if timings.contains(&TimingStatus::Exceeded) {
+            println!(
+                "! over the time limit of {} ms",
+                self.judge.time_limit_millis
+            );
+        }
+        if timings.contains(&TimingStatus::Risky) {
+            println!(
+                "~ within {} ms of the time limit of {} ms",
+                self.judge.margin_millis, self.judge.time_limit_millis
+            );
+        }
+    }
+
+    /// Prints the number of cases at risk of TLE per solution, and the slowest seeds of the
+    /// primary solution.
+    fn print_timing(&self) {
+        println!();
+        for solution in &self.solutions {
+            let counts = self
+                .seed_solution_results
+                .values()
+                .map(|solution_results| solution_results[solution].timing(&self.judge))
+                .counts();
+            let over = counts.get(&TimingStatus::Exceeded).unwrap_or(&0);
+            let near = counts.get(&TimingStatus::Risky).unwrap_or(&0);
+            if over + near > 0 {
+                println!(
+                    "{}: {} over the time limit, {} near the time limit",
+                    solution.inner(),
+                    over,
+                    near
+                );
+            }
+        }
+
+        println!(
+            "slowest seeds of {} (time limit {} ms):",
+            self.primary_solution.inner(),
+            self.judge.time_limit_millis
+        );
+        let slowest = self
+            .seed_solution_results
+            .iter()
+            .map(|(seed, solution_results)| (seed, &solution_results[&self.primary_solution]))
+            .sorted_by_key(|(seed, result)| {
+                let millis = result
+                    .duration_millis
+                    .max(result.cpu_time_millis.unwrap_or(0));
+                (Reverse(millis), *seed)
+            })
+            .take(SLOWEST_SEEDS);
+        for (seed, result) in slowest {
+            println!(
+                "  seed {}: {} ms wall, {} cpu{}",
+                seed.inner(),
+                result.duration_millis,
+                format_cpu_time(result.cpu_time_millis),
+                match result.timing(&self.judge) {
+                    TimingStatus::Safe => "",
+                    TimingStatus::Risky => " (near the limit)",
+                    TimingStatus::Exceeded => " (over the limit)",
+                }
+            );
+        }
+    }
+
+    /// Prints the average relative score of each solution per (bucketed) value of the parameter
+    /// `key`.
+    fn print_groups(&self, key: &str) {
+        let key_index = InitInput::description_keys()
+            .iter()
+            .position(|k| *k == key)
+            .expect("unknown parameter to group by");
+        let seeds = self.seed_solution_results.keys().sorted().collect_vec();
+        let values = seeds
+            .iter()
+            .map(|seed| {
+                let primary_result = &self.seed_solution_results[*seed][&self.primary_solution];
+                primary_result.init_input.description_values()[key_index].clone()
+            })
+            .collect_vec();
+
+        let mut table = Table::new();
+        table.header.push(TableCell {
+            content: key.to_string(),
+            alignment: Alignment::Left,
+        });
+        table.header.push(TableCell {
+            content: "cases".to_string(),
+            alignment: Alignment::Right,
+        });
+        for solution in &self.solutions {
+            table.header.push(TableCell {
+                content: solution.inner().to_string(),
+                alignment: Alignment::Right,
+            });
+        }
+
+        for group in group_values(&values, self.buckets) {
+            let mut row = vec![
+                TableCell {
+                    content: group.label,
+                    alignment: Alignment::Left,
+                },
+                TableCell {
+                    content: group.members.len().to_string(),
+                    alignment: Alignment::Right,
+                },
+            ];
+
+            let mut total_relative_scores = vec![0.0; self.solutions.len()];
+            for &member in &group.members {
+                let relative_scores =
+                    self.relative_scores_of(&self.seed_solution_results[seeds[member]]);
+                for (total, relative_score) in izip!(&mut total_relative_scores, relative_scores) {
+                    *total += relative_score;
+                }
+            }
+            for total in total_relative_scores {
+                row.push(TableCell {
+                    content: self.scoring.format(total / group.members.len() as f64),
+                    alignment: Alignment::Right,
+                });
+            }
+
+            table.body.push(row);
+        }
+
+        println!();
+        table.print();
+    }
+
+    fn print_statistics(&self) {
+        let primary_index = self
+            .solutions
+            .iter()
+            .position(|solution| solution == &self.primary_solution)
+            .expect("primary solution is not selected");
+        let seed_relative_scores = self
+            .seed_solution_results
+            .values()
+            .map(|solution_results| self.relative_scores_of(solution_results))
+            .collect_vec();
+
+        let mut table = Table::new();
+        for (content, alignment) in [
+            ("solution", Alignment::Left),
+            ("mean", Alignment::Right),
+            ("median", Alignment::Right),
+            ("stddev", Alignment::Right),
+            ("min", Alignment::Right),
+            ("max", Alignment::Right),
+            ("W / T / L", Alignment::Right),
+            ("sign test p", Alignment::Right),
+            ("relative diff (95% CI)", Alignment::Right),
+        ] {
+            table.header.push(TableCell {
+                content: content.to_string(),
+                alignment,
+            });
+        }
+
+        for (index, solution) in self.solutions.iter().enumerate() {
+            // Descriptive statistics only cover successful cases.
+            let scores = self
+                .seed_solution_results
+                .values()
+                .filter_map(|solution_results| solution_results[solution].score.as_ref().ok())
+                .map(|&score| score as f64)
+                .collect_vec();
+            let summary = Summary::of(&scores);
+            let describe = |f: fn(&Summary) -> f64| {
+                summary
+                    .as_ref()
+                    .map_or_else(|| "-".to_string(), |summary| format!("{:.1}", f(summary)))
+            };
+
+            let mut row = vec![solution.inner().to_string()];
+            row.push(describe(|s| s.mean));
+            row.push(describe(|s| s.median));
+            row.push(describe(|s| s.stddev));
+            row.push(describe(|s| s.min));
+            row.push(describe(|s| s.max));
+
+            if index == primary_index {
+                row.extend(["(primary)", "-", "-"].map(str::to_string));
+            } else {
+                let (wins, ties, losses) = self.win_tie_loss(solution);
+                row.push(format!("{} / {} / {}", wins, ties, losses));
+                row.push(format!("{:.4}", sign_test_p_value(wins, losses)));
+
+                let differences = seed_relative_scores
+                    .iter()
+                    .map(|relative_scores| relative_scores[index] - relative_scores[primary_index])
+                    .collect_vec();
+                let mean_difference = differences.iter().sum::<f64>() / differences.len() as f64;
+                row.push(match bootstrap_mean_ci(&differences) {
+                    Some((low, high)) => format!(
+                        "{} [{}, {}]",
+                        self.scoring.format(mean_difference),
+                        self.scoring.format(low),
+                        self.scoring.format(high)
+                    ),
+                    None => "-".to_string(),
+                });
+            }
+
+            table.body.push(
+                row.into_iter()
+                    .enumerate()
+                    .map(|(column, content)| TableCell {
+                        content,
+                        alignment: if column == 0 {
+                            Alignment::Left
+                        } else {
+                            Alignment::Right
+                        },
+                    })
+                    .collect_vec(),
+            );
+        }
+
+        println!();
+        table.print();
+    }
+
+    /// Counts seeds where `solution` is better than, as good as and worse than the primary
+    /// solution. A failure is worse than any score; two failures are a tie.
+    fn win_tie_loss(&self, solution: &Solution) -> (usize, usize, usize) {
+        self.seed_solution_results.values().fold(
+            (0, 0, 0),
+            |(wins, ties, losses), solution_results| {
+                let score = solution_results[solution].score.as_ref().ok();
+                let primary_score = solution_results[&self.primary_solution].score.as_ref().ok();
+                match (score, primary_score) {
+                    (Some(score), Some(primary_score)) if score == primary_score => {
+                        (wins, ties + 1, losses)
+                    }
+                    (Some(&score), Some(&primary_score))
+                        if self.absolute_better.is_former(score, primary_score) =>
+                    {
+                        (wins + 1, ties, losses)
+                    }
+                    (Some(_), Some(_)) | (None, Some(_)) => (wins, ties, losses + 1),
+                    (Some(_), None) => (wins + 1, ties, losses),
+                    (None, None) => (wins, ties + 1, losses),
+                }
+            },
+        )
+    }
+
+    /// Relative scores on one seed, in the order of `self.solutions`.
+    fn relative_scores_of(&self, solution_results: &HashMap<Solution, TestCaseResult>) -> Vec<f64> {
+        let scores = self
+            .solutions
+            .iter()
+            .map(|solution| solution_results[solution].score.as_ref().ok().copied())
+            .collect_vec();
+
+        self.scoring.relative_scores(self.absolute_better, &scores)
+    }
+
+    fn print_failures(&self) {
+        for solution in &self.solutions {
+            let failures = self
+                .seed_solution_results
+                .iter()
+                .sorted_by_key(|(seed, _)| *seed)
+                .filter_map(|(seed, solution_results)| {
+                    solution_results[solution]
+                        .score
+                        .as_ref()
+                        .err()
+                        .map(|failure| (seed, failure))
+                })
+                .collect_vec();
+            if failures.is_empty() {
+                continue;
+            }
+
+            let counts = failures
+                .iter()
+                .counts_by(|(_, failure)| failure.label())
+                .into_iter()
+                .sorted()
+                .map(|(label, count)| format!("{count} {label}"))
+                .join(", ");
+            println!("{}: {}", solution.inner(), counts);
+
+            if solution != &self.primary_solution {
+                continue;
+            }
+
+            for (seed, failure) in failures.iter().take(MAX_FAILURE_DETAILS) {
+                let Some((exit_status, stderr_tail)) = failure.details() else {
+                    continue;
+                };
+                println!(
+                    "  seed {}: {} ({})",
+                    seed.inner(),
+                    failure.label(),
+                    exit_status
+                );
+                for line in stderr_tail.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
     }
 
     fn transpose_results(
         results: &HashMap<Solution, HashMap<Seed, TestCaseResult>>,
+        primary_solution: &Solution,
     ) -> HashMap<Seed, HashMap<Solution, TestCaseResult>> {
         let solutions = results.keys().cloned().collect_vec();
-        let seeds = results[&*PRIMARY_SOLUTION].keys().cloned().collect_vec();
+        let seeds = results[primary_solution].keys().cloned().collect_vec();
 
         // Transpose `Solution -> Seed -> Result` to `Seed -> Solution -> Result`
         seeds
@@ -565,11 +1573,45 @@ impl TablePrinter {
         }
 
         // Solutions
-        for solution in &*SOLUTIONS {
+        for solution in &self.solutions {
             table.header.push(TableCell {
                 content: solution.inner().to_string(),
                 alignment: Alignment::Left,
             });
+            if self.show_cpu {
+                table.header.push(TableCell {
+                    content: format!("{} cpu", solution.inner()),
+                    alignment: Alignment::Left,
+                });
+            }
+            if self.show_memory {
+                table.header.push(TableCell {
+                    content: format!("{} mem", solution.inner()),
+                    alignment: Alignment::Left,
+                });
+            }
+            for key in self.metric_keys(solution) {
+                table.header.push(TableCell {
+                    content: format!("{} {}", solution.inner(), key),
+                    alignment: Alignment::Left,
+                });
+            }
+        }
+
+        // Comparison with the snapshot
+        if let Some(snapshot) = &self.comparison {
+            table.header.push(TableCell {
+                content: format!("vs {}", snapshot.name),
+                alignment: Alignment::Left,
+            });
+        }
+
+        // Validation errors
+        if self.has_validation_errors() {
+            table.header.push(TableCell {
+                content: "validation".to_string(),
+                alignment: Alignment::Left,
+            });
         }
     }
 
@@ -579,22 +1621,25 @@ impl TablePrinter {
             self.seed_solution_results
                 .iter()
                 .sorted_by_key(|(_, solution_results)| {
-                    solution_results[&*PRIMARY_SOLUTION]
+                    solution_results[&self.primary_solution]
                         .score
-                        .map(|x| ABSOLUTE_BETTER.can_be_sorted_by_this_key(x))
+                        .as_ref()
+                        .map(|&x| self.absolute_better.can_be_sorted_by_this_key(x))
                 });
 
-        let mut solution_total_absolute_score = SOLUTIONS
+        let mut solution_total_absolute_score = self
+            .solutions
             .iter()
             .map(|s| (s.clone(), 0))
             .collect::<HashMap<_, _>>();
-        let mut solution_total_relative_score = SOLUTIONS
+        let mut solution_total_relative_score = self
+            .solutions
             .iter()
             .map(|s| (s.clone(), 0.0))
             .collect::<HashMap<_, _>>();
 
         for (seed, solution_results) in sorted_results {
-            let primary_result = &solution_results[&*PRIMARY_SOLUTION];
+            let primary_result = &solution_results[&self.primary_solution];
 
             let mut row = vec![];
 
@@ -613,25 +1658,54 @@ impl TablePrinter {
             }
 
             // Solutions
-            let best_score = ABSOLUTE_BETTER
-                .is_which(solution_results.values().flat_map(|r| r.score))
-                .unwrap_or_else(|| ABSOLUTE_BETTER.is_always_better_than_this_value());
-            for solution in &*SOLUTIONS {
+            let relative_scores = self.relative_scores_of(solution_results);
+            for (solution, relative_score) in izip!(&self.solutions, relative_scores) {
                 let result = &solution_results[solution];
-                let absolute_score = result
-                    .score
-                    .unwrap_or_else(|_| ABSOLUTE_BETTER.is_always_better_than_this_value());
-                let relative_score =
-                    ABSOLUTE_BETTER.make_relative_goodness(absolute_score, best_score);
-                let absolute_score_display = if result.score.is_ok() {
-                    format!("{}", absolute_score)
+                let absolute_score =
+                    result.score.as_ref().copied().unwrap_or_else(|_| {
+                        self.absolute_better.is_always_better_than_this_value()
+                    });
+                let stale_marker = if result.is_stale(&self.source_hashes[solution]) {
+                    "*"
                 } else {
-                    "ERROR".to_string()
+                    ""
                 };
+                let timing_marker = result.timing(&self.judge).marker();
                 row.push(TableCell {
-                    content: format!("{:>10} / {:>10.8}", absolute_score_display, relative_score),
+                    content: format!(
+                        "{:>10} / {:>10}",
+                        format!(
+                            "{}{}{}",
+                            stale_marker,
+                            timing_marker,
+                            result.score_display()
+                        ),
+                        self.scoring.format(relative_score)
+                    ),
                     alignment: Alignment::Right,
                 });
+                if self.show_cpu {
+                    row.push(TableCell {
+                        content: format_cpu_time(result.cpu_time_millis),
+                        alignment: Alignment::Right,
+                    });
+                }
+                if self.show_memory {
+                    row.push(TableCell {
+                        content: format_memory(result.peak_memory_kib),
+                        alignment: Alignment::Right,
+                    });
+                }
+                for key in self.metric_keys(solution) {
+                    row.push(TableCell {
+                        content: result
+                            .metrics
+                            .get(key)
+                            .cloned()
+                            .unwrap_or_else(|| "-".to_string()),
+                        alignment: Alignment::Right,
+                    });
+                }
                 *solution_total_absolute_score
                     .get_mut(solution)
                     .expect("unknown solution") += absolute_score;
@@ -640,6 +1714,43 @@ impl TablePrinter {
                     .expect("unknown solution") += relative_score;
             }
 
+            // Comparison with the snapshot
+            if let Some(snapshot) = &self.comparison {
+                let content = match snapshot.results.get(seed) {
+                    None => "-".to_string(),
+                    Some(old_result) => match Self::score_delta(old_result, primary_result) {
+                        Some(delta) => format!("{:+}", delta),
+                        None => format!(
+                            "{} -> {}",
+                            old_result.score_display(),
+                            primary_result.score_display()
+                        ),
+                    },
+                };
+                row.push(TableCell {
+                    content,
+                    alignment: Alignment::Right,
+                });
+            }
+
+            // Validation errors
+            if self.has_validation_errors() {
+                let content = self
+                    .solutions
+                    .iter()
+                    .filter_map(|solution| match &solution_results[solution].score {
+                        Err(Failure::WrongAnswer { message }) => {
+                            Some(format!("{}: {}", solution.inner(), message))
+                        }
+                        _ => None,
+                    })
+                    .join("; ");
+                row.push(TableCell {
+                    content,
+                    alignment: Alignment::Left,
+                });
+            }
+
             table.body.push(row);
         }
 
@@ -667,15 +1778,107 @@ impl TablePrinter {
         }
 
         // Solutions
-        for solution in &*SOLUTIONS {
+        for solution in &self.solutions {
             table.footer.push(TableCell {
                 content: format!(
-                    "{:>10} / {:>10.8}",
+                    "{:>10} / {:>10}",
                     solution_total_absolute_score[solution],
-                    solution_total_relative_score[solution]
+                    self.scoring.format(solution_total_relative_score[solution])
                 ),
                 alignment: Alignment::Right,
             });
+
+            // The worst case is what matters for the judge.
+            let results = || {
+                self.seed_solution_results
+                    .values()
+                    .map(|solution_results| &solution_results[solution])
+            };
+            if self.show_cpu {
+                let max = results().filter_map(|result| result.cpu_time_millis).max();
+                table.footer.push(TableCell {
+                    content: format!("max {}", format_cpu_time(max)),
+                    alignment: Alignment::Right,
+                });
+            }
+            if self.show_memory {
+                let max = results().filter_map(|result| result.peak_memory_kib).max();
+                table.footer.push(TableCell {
+                    content: format!("max {}", format_memory(max)),
+                    alignment: Alignment::Right,
+                });
+            }
+            for key in self.metric_keys(solution) {
+                // Only numeric metrics are averaged.
+                let values: Option<Vec<f64>> = results()
+                    .filter_map(|result| result.metrics.get(key))
+                    .map(|value| value.parse().ok())
+                    .collect();
+                let content = values
+                    .and_then(|values| Summary::of(&values))
+                    .map_or_else(String::new, |summary| format!("mean {:.1}", summary.mean));
+                table.footer.push(TableCell {
+                    content,
+                    alignment: Alignment::Right,
+                });
+            }
+        }
+
+        // Comparison with the snapshot. Only seeds successful in both runs are summed up.
+        if let Some(snapshot) = &self.comparison {
+            let total_delta: i64 = self
+                .seed_solution_results
+                .iter()
+                .filter_map(|(seed, solution_results)| {
+                    Self::score_delta(
+                        snapshot.results.get(seed)?,
+                        &solution_results[&self.primary_solution],
+                    )
+                })
+                .sum();
+            table.footer.push(TableCell {
+                content: format!("{:+}", total_delta),
+                alignment: Alignment::Right,
+            });
+        }
+
+        // Validation errors
+        if self.has_validation_errors() {
+            table.footer.push(TableCell {
+                content: "".to_string(),
+                alignment: Alignment::Left,
+            });
+        }
+    }
+
+    /// Keys of the metrics reported by the solution in any case, if they are shown.
+    fn metric_keys(&self, solution: &Solution) -> Vec<&str> {
+        if !self.show_metrics {
+            return vec![];
         }
+
+        self.seed_solution_results
+            .values()
+            .flat_map(|solution_results| solution_results[solution].metrics.keys())
+            .map(|key| &**key)
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Whether any output was rejected, in which case the errors get their own column.
+    fn has_validation_errors(&self) -> bool {
+        self.seed_solution_results
+            .values()
+            .flat_map(|solution_results| solution_results.values())
+            .any(|result| matches!(result.score, Err(Failure::WrongAnswer { .. })))
+    }
+
+    /// Difference of the absolute scores, or `None` if either of them failed.
+    fn score_delta(old_result: &TestCaseResult, new_result: &TestCaseResult) -> Option<i64> {
+        let old_score = *old_result.score.as_ref().ok()?;
+        let new_score = *new_result.score.as_ref().ok()?;
+
+        Some(new_score as i64 - old_score as i64)
     }
 }
@@ -0,0 +1,66 @@
+use miette::{ensure, IntoDiagnostic, Result, WrapErr};
+use std::{
+    env::consts::EXE_SUFFIX,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tempfile::TempDir;
+
+/// Length of the hash prefix in the file name of the frozen binary.
+const HASH_PREFIX_LEN: usize = 12;
+
+/// A copy of the solution binary for one run, so that rebuilding during the run does not change
+/// the binary under test. The copy is removed when this is dropped.
+#[derive(Debug)]
+pub struct FrozenBinary {
+    _dir: TempDir,
+    pub path: PathBuf,
+    /// SHA-256 of the binary.
+    pub hash: String,
+}
+
+impl FrozenBinary {
+    /// Builds the solution binary and copies it into a new directory under `<testing dir>/bin`.
+    pub fn build(testing_dir: &Path) -> Result<Self> {
+        eprintln!("building binary");
+        let status = Command::new("cargo")
+            .args(["build", "--release", "--bin", "main"])
+            .status()
+            .into_diagnostic()
+            .wrap_err("failed to execute cargo build")?;
+        ensure!(status.success(), "failed to build solution binary");
+
+        let built_path = Path::new("target")
+            .join("release")
+            .join(format!("main{EXE_SUFFIX}"));
+        let contents = fs::read(&built_path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read {}", built_path.display()))?;
+        let hash = sha256::digest(&contents[..]);
+
+        let bin_dir = testing_dir.join("bin");
+        fs::create_dir_all(&bin_dir)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to create {}", bin_dir.display()))?;
+        let dir = tempfile::Builder::new()
+            .prefix("run-")
+            .tempdir_in(&bin_dir)
+            .into_diagnostic()
+            .wrap_err("failed to create directory for the binary")?;
+        let path = dir
+            .path()
+            .join(format!("main-{}{EXE_SUFFIX}", &hash[..HASH_PREFIX_LEN]));
+        // `fs::copy` keeps the permission bits, so the copy stays executable.
+        fs::copy(&built_path, &path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to copy binary to {}", path.display()))?;
+        eprintln!("testing binary {}", path.display());
+
+        Ok(Self {
+            _dir: dir,
+            path,
+            hash,
+        })
+    }
+}
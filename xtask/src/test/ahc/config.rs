@@ -0,0 +1,138 @@
+use miette::{ensure, IntoDiagnostic, Result, WrapErr};
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{scoring::Scoring, timing::JudgeLimits, AbsoluteBetterIs};
+
+/// Project configuration read from `xtask.toml` at the project root.
+///
+/// Every field is optional; missing fields (or a missing file) fall back to the defaults below.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Whether a larger or a smaller score is better.
+    pub objective: AbsoluteBetterIs,
+    /// How relative scores and their totals are computed.
+    pub scoring: Scoring,
+    /// Regex to extract the score from the visualizer output. It must have a named capture
+    /// `score`.
+    pub score_regex: String,
+    /// Directory containing `seeds.txt`, `in`, `out` and `tools`.
+    pub testing_dir: PathBuf,
+    pub tools: ToolNames,
+    pub judge: JudgeLimits,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            objective: AbsoluteBetterIs::Maximum,
+            scoring: Scoring::default(),
+            score_regex: r"Score = (?<score>\d*)".to_string(),
+            testing_dir: PathBuf::from("testing"),
+            tools: ToolNames::default(),
+            judge: JudgeLimits::default(),
+        }
+    }
+}
+
+impl Config {
+    pub const FILE_NAME: &'static str = "xtask.toml";
+
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&contents)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+        config.compile_score_regex()?;
+
+        Ok(config)
+    }
+
+    pub fn compile_score_regex(&self) -> Result<Regex> {
+        let re = Regex::new(&self.score_regex)
+            .into_diagnostic()
+            .wrap_err("invalid score_regex")?;
+        ensure!(
+            re.capture_names().any(|name| name == Some("score")),
+            "score_regex must have a named capture `score`"
+        );
+
+        Ok(re)
+    }
+}
+
+/// File names of the official tools under `tools/target/release` in the testing directory.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolNames {
+    pub gen: String,
+    pub vis: String,
+    /// Used only when it exists, i.e. for interactive problems.
+    pub tester: String,
+}
+
+impl Default for ToolNames {
+    fn default() -> Self {
+        Self {
+            gen: "gen".to_string(),
+            vis: "vis".to_string(),
+            tester: "tester".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(
+            r#"
+            objective = "minimize"
+            scoring = "relative"
+            testing_dir = "work"
+
+            [tools]
+            vis = "score"
+
+            [judge]
+            time_limit_millis = 2000
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(config.objective, AbsoluteBetterIs::Minimum));
+        assert!(matches!(config.scoring, Scoring::Relative));
+        assert_eq!(config.testing_dir, PathBuf::from("work"));
+        assert_eq!(config.tools.gen, "gen");
+        assert_eq!(config.tools.vis, "score");
+        assert_eq!(config.judge.time_limit_millis, 2000);
+        assert_eq!(
+            config.judge.margin_millis,
+            JudgeLimits::default().margin_millis
+        );
+        assert!(config.compile_score_regex().is_ok());
+    }
+
+    #[test]
+    fn test_score_regex_requires_capture() {
+        let config = Config {
+            score_regex: r"Score = \d+".to_string(),
+            ..Config::default()
+        };
+
+        assert!(config.compile_score_regex().is_err());
+    }
+}
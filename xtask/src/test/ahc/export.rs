@@ -0,0 +1,242 @@
+use itertools::Itertools;
+use miette::{bail, IntoDiagnostic, Result, WrapErr};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Outcome of a case which produced a valid score.
+pub const OUTCOME_OK: &str = "OK";
+
+/// One exported row, i.e. the result of a solution on a seed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Record {
+    pub solution: String,
+    pub seed: String,
+    /// `InitInput` description keys and values, in order.
+    #[serde(serialize_with = "serialize_parameters")]
+    pub parameters: Vec<(String, String)>,
+    /// `None` if the case failed.
+    pub score: Option<u64>,
+    /// `OK` or the label of the failure.
+    pub outcome: String,
+    pub duration_millis: i64,
+    /// `None` if not measured.
+    pub cpu_time_millis: Option<i64>,
+    /// `None` if not measured.
+    pub peak_memory_kib: Option<u64>,
+    /// Values reported by `library::metric!`.
+    pub metrics: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    JsonLines,
+}
+
+impl Format {
+    pub fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(Self::Csv),
+            Some("jsonl") => Ok(Self::JsonLines),
+            _ => bail!(
+                "unsupported export format: {} (expected .csv or .jsonl)",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Writes `records` to `path`, as CSV or JSON Lines depending on the extension.
+pub fn write(path: &Path, records: &[Record]) -> Result<()> {
+    let contents = match Format::of(path)? {
+        Format::Csv => to_csv(records),
+        Format::JsonLines => to_jsonl(records)?,
+    };
+
+    let mut file = BufWriter::new(
+        File::create(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to create {}", path.display()))?,
+    );
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.flush())
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+fn to_csv(records: &[Record]) -> String {
+    let mut csv = String::new();
+    let parameter_keys: Vec<&str> = records
+        .first()
+        .map(|record| record.parameters.iter().map(|(key, _)| &**key).collect())
+        .unwrap_or_default();
+    // Solutions may report different metrics, so the columns are the union of them.
+    let metric_keys = records
+        .iter()
+        .flat_map(|record| record.metrics.keys())
+        .sorted()
+        .dedup()
+        .collect_vec();
+    let metric_columns = metric_keys
+        .iter()
+        .map(|key| format!("metric:{key}"))
+        .collect_vec();
+
+    let header = ["solution", "seed"]
+        .into_iter()
+        .chain(parameter_keys)
+        .chain([
+            "score",
+            "outcome",
+            "duration_millis",
+            "cpu_time_millis",
+            "peak_memory_kib",
+        ])
+        .chain(metric_columns.iter().map(|column| &**column));
+    push_csv_row(&mut csv, header);
+
+    for record in records {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let score = optional(record.score.map(|score| score.to_string()));
+        let duration = record.duration_millis.to_string();
+        let cpu_time = optional(record.cpu_time_millis.map(|millis| millis.to_string()));
+        let peak_memory = optional(record.peak_memory_kib.map(|kib| kib.to_string()));
+        let row = [&*record.solution, &*record.seed]
+            .into_iter()
+            .chain(record.parameters.iter().map(|(_, value)| &**value))
+            .chain([
+                &*score,
+                &*record.outcome,
+                &*duration,
+                &*cpu_time,
+                &*peak_memory,
+            ])
+            .chain(
+                metric_keys
+                    .iter()
+                    .map(|key| record.metrics.get(*key).map_or("", |value| &**value)),
+            );
+        push_csv_row(&mut csv, row);
+    }
+
+    csv
+}
+
+fn push_csv_row<'a>(csv: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        csv.push_str(&escape_csv_field(field));
+    }
+    csv.push('\n');
+}
+
+/// Quotes the field as in RFC 4180 if it contains a separator, a quote or a line break.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_jsonl(records: &[Record]) -> Result<String> {
+    let mut jsonl = String::new();
+    for record in records {
+        jsonl += &serde_json::to_string(record)
+            .into_diagnostic()
+            .wrap_err("failed to serialize record into JSON")?;
+        jsonl.push('\n');
+    }
+
+    Ok(jsonl)
+}
+
+/// Serializes parameters as a JSON object while keeping the order of the keys.
+fn serialize_parameters<S: Serializer>(
+    parameters: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(parameters.len()))?;
+    for (key, value) in parameters {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                solution: "naive".to_string(),
+                seed: "0".to_string(),
+                parameters: vec![
+                    ("N".to_string(), "10".to_string()),
+                    ("name".to_string(), "a,\"b\"".to_string()),
+                ],
+                score: Some(123),
+                outcome: OUTCOME_OK.to_string(),
+                duration_millis: 45,
+                cpu_time_millis: Some(40),
+                peak_memory_kib: Some(3072),
+                metrics: BTreeMap::from([("iterations".to_string(), "1000".to_string())]),
+            },
+            Record {
+                solution: "naive".to_string(),
+                seed: "1".to_string(),
+                parameters: vec![
+                    ("N".to_string(), "20".to_string()),
+                    ("name".to_string(), "c".to_string()),
+                ],
+                score: None,
+                outcome: "TLE".to_string(),
+                duration_millis: 6000,
+                cpu_time_millis: None,
+                peak_memory_kib: None,
+                metrics: BTreeMap::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::of(Path::new("a/results.csv")).unwrap(), Format::Csv);
+        assert_eq!(
+            Format::of(Path::new("results.jsonl")).unwrap(),
+            Format::JsonLines
+        );
+        assert!(Format::of(Path::new("results.json")).is_err());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            to_csv(&records()),
+            "solution,seed,N,name,score,outcome,duration_millis,cpu_time_millis,peak_memory_kib,\
+             metric:iterations\n\
+             naive,0,10,\"a,\"\"b\"\"\",123,OK,45,40,3072,1000\n\
+             naive,1,20,c,,TLE,6000,,,\n"
+        );
+    }
+
+    #[test]
+    fn test_to_jsonl() {
+        let jsonl = to_jsonl(&records()).unwrap();
+        let lines = jsonl.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            r#"{"solution":"naive","seed":"1","parameters":{"N":"20","name":"c"},"score":null,"outcome":"TLE","duration_millis":6000,"cpu_time_millis":null,"peak_memory_kib":null,"metrics":{}}"#
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use itertools::Itertools;
+
+/// Number of buckets for numeric parameters unless `--buckets` is specified.
+pub const DEFAULT_BUCKETS: usize = 4;
+
+/// A set of seeds sharing the same (bucketed) parameter value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub label: String,
+    /// Indices into the values passed to `group_values`.
+    pub members: Vec<usize>,
+}
+
+/// Groups parameter values. If every value is numeric and there are more distinct values than
+/// `buckets`, they are split into `buckets` ranges of equal width. Otherwise each distinct value
+/// forms its own group.
+pub fn group_values(values: &[String], buckets: usize) -> Vec<Group> {
+    let numbers = values
+        .iter()
+        .map(|value| value.parse::<f64>().ok().filter(|x| x.is_finite()))
+        .collect::<Option<Vec<_>>>();
+
+    match numbers {
+        Some(numbers) if numbers.iter().copied().map(f64::to_bits).unique().count() > buckets => {
+            bucket_numbers(&numbers, buckets)
+        }
+        Some(numbers) => group_by_label(&numbers, |&x| (x, x.to_string())),
+        None => group_by_label(values, |value| (0.0, value.clone())),
+    }
+}
+
+fn bucket_numbers(numbers: &[f64], buckets: usize) -> Vec<Group> {
+    let (min, max) = numbers
+        .iter()
+        .copied()
+        .minmax_by(f64::total_cmp)
+        .into_option()
+        .expect("no values to bucket");
+    let width = (max - min) / buckets as f64;
+    let bucket_of = |x: f64| (((x - min) / width) as usize).min(buckets - 1);
+
+    (0..buckets)
+        .map(|bucket| {
+            let low = min + width * bucket as f64;
+            let high = min + width * (bucket + 1) as f64;
+            // The last bucket also contains `max`.
+            let closing = if bucket + 1 == buckets { ']' } else { ')' };
+            Group {
+                label: format!("[{}, {}{}", round(low), round(high), closing),
+                members: numbers
+                    .iter()
+                    .positions(|&x| bucket_of(x) == bucket)
+                    .collect(),
+            }
+        })
+        .filter(|group| !group.members.is_empty())
+        .collect()
+}
+
+fn group_by_label<T>(values: &[T], key: impl Fn(&T) -> (f64, String)) -> Vec<Group> {
+    values
+        .iter()
+        .enumerate()
+        .into_group_map_by(|(_, value)| key(value).1)
+        .into_iter()
+        .map(|(label, members)| {
+            let order = key(members[0].1).0;
+            let members = members.into_iter().map(|(index, _)| index).collect_vec();
+            (order, Group { label, members })
+        })
+        .sorted_by(|(a_order, a), (b_order, b)| {
+            a_order
+                .total_cmp(b_order)
+                .then_with(|| a.label.cmp(&b.label))
+        })
+        .map(|(_, group)| group)
+        .collect()
+}
+
+/// Rounds bucket boundaries to keep the labels short.
+fn round(x: f64) -> f64 {
+    (x * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_bucket_numbers() {
+        let groups = group_values(&values(&["0", "10", "5", "9", "1", "4"]), 2);
+
+        assert_eq!(
+            groups,
+            vec![
+                Group {
+                    label: "[0, 5)".to_string(),
+                    members: vec![0, 4, 5],
+                },
+                Group {
+                    label: "[5, 10]".to_string(),
+                    members: vec![1, 2, 3],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_few_distinct_numbers_are_not_bucketed() {
+        let groups = group_values(&values(&["10", "2", "10"]), 4);
+
+        assert_eq!(
+            groups.iter().map(|group| &*group.label).collect_vec(),
+            vec!["2", "10"]
+        );
+        assert_eq!(groups[1].members, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_categorical_values() {
+        let groups = group_values(&values(&["b", "a", "1", "b"]), 1);
+
+        assert_eq!(
+            groups.iter().map(|group| &*group.label).collect_vec(),
+            vec!["1", "a", "b"]
+        );
+        assert_eq!(groups[2].members, vec![0, 3]);
+    }
+}
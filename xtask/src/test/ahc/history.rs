@@ -0,0 +1,251 @@
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+use miette::{bail, IntoDiagnostic, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{Seed, Solution, TestCaseResult};
+
+/// Name of the results file in each snapshot directory.
+const RESULTS_FILE_NAME: &str = "results.json";
+
+/// Snapshot name which designates the most recent snapshot.
+pub const LATEST: &str = "latest";
+
+/// Archive of primary-solution runs under `<testing dir>/history/<timestamp>-<git hash>/`.
+#[derive(Debug, Clone)]
+pub struct History {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    #[serde(skip)]
+    pub name: String,
+    pub created_at: DateTime<Local>,
+    pub git_hash: Option<String>,
+    pub solution: Solution,
+    pub results: HashMap<Seed, TestCaseResult>,
+}
+
+impl History {
+    pub fn new(testing_dir: &Path) -> Self {
+        Self {
+            dir: testing_dir.join("history"),
+        }
+    }
+
+    /// Archives the results of a run of `solution`, and returns the name of the snapshot.
+    pub fn archive(
+        &self,
+        solution: &Solution,
+        results: &HashMap<Seed, TestCaseResult>,
+    ) -> Result<String> {
+        let created_at = Local::now();
+        let git_hash = current_git_hash();
+        let base_name = format!(
+            "{}-{}",
+            created_at.format("%Y%m%d-%H%M%S"),
+            git_hash.as_deref().unwrap_or("nogit")
+        );
+
+        fs::create_dir_all(&self.dir)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to create {}", self.dir.display()))?;
+        // Runs archived within the same second share the timestamp, so number the later ones.
+        let mut name = base_name.clone();
+        let mut snapshot_dir = self.dir.join(&name);
+        for number in 2.. {
+            match fs::create_dir(&snapshot_dir) {
+                Ok(()) => break,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    name = format!("{base_name}-{number}");
+                    snapshot_dir = self.dir.join(&name);
+                }
+                Err(e) => {
+                    return Err(e)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("failed to create {}", snapshot_dir.display()))
+                }
+            }
+        }
+        let file = File::create(snapshot_dir.join(RESULTS_FILE_NAME))
+            .into_diagnostic()
+            .wrap_err("failed to create snapshot file")?;
+        let snapshot = Snapshot {
+            name: name.clone(),
+            created_at,
+            git_hash,
+            solution: solution.clone(),
+            results: results.clone(),
+        };
+        serde_json::to_writer(file, &snapshot)
+            .into_diagnostic()
+            .wrap_err("failed to serialize snapshot into JSON")?;
+
+        Ok(name)
+    }
+
+    /// Loads a snapshot by its name, a unique prefix of its name, a path to its directory, or
+    /// `latest`.
+    pub fn load(&self, query: &str) -> Result<Snapshot> {
+        let snapshot_dir = self.resolve(query)?;
+        let name = snapshot_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| query.to_string());
+        let file = File::open(snapshot_dir.join(RESULTS_FILE_NAME))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to open snapshot {}", name))?;
+        let snapshot: Snapshot = serde_json::from_reader(file)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse snapshot {}", name))?;
+
+        Ok(Snapshot { name, ..snapshot })
+    }
+
+    fn resolve(&self, query: &str) -> Result<PathBuf> {
+        let path = Path::new(query);
+        if path.join(RESULTS_FILE_NAME).exists() {
+            return Ok(path.to_owned());
+        }
+
+        // Snapshot names start with the timestamp, so they sort chronologically.
+        let names = self.snapshot_names()?;
+        let candidates = if query == LATEST {
+            names.last().into_iter().collect_vec()
+        } else if let Some(name) = names.iter().find(|name| *name == query) {
+            // A name is also a prefix of the names numbered after it.
+            vec![name]
+        } else {
+            names
+                .iter()
+                .filter(|name| name.starts_with(query))
+                .collect_vec()
+        };
+
+        match &*candidates {
+            [name] => Ok(self.dir.join(name)),
+            [] => bail!("no snapshot matches {}", query),
+            _ => bail!(
+                "ambiguous snapshot {}: {}",
+                query,
+                candidates.iter().join(", ")
+            ),
+        }
+    }
+
+    fn snapshot_names(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.dir)
+            .into_diagnostic()
+            .wrap_err("failed to list snapshots")?
+        {
+            let entry = entry
+                .into_diagnostic()
+                .wrap_err("failed to read snapshot entry")?;
+            if entry.path().join(RESULTS_FILE_NAME).exists() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+
+        Ok(names)
+    }
+}
+
+/// Short hash of `HEAD`, suffixed with `-dirty` when the working tree has uncommitted changes.
+/// `None` outside of a git repository.
+fn current_git_hash() -> Option<String> {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let hash = git(&["rev-parse", "--short", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+
+    Some(if dirty { format!("{hash}-dirty") } else { hash })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history_with(names: &[&str]) -> (tempfile::TempDir, History) {
+        let testing_dir = tempfile::tempdir().unwrap();
+        let history = History::new(testing_dir.path());
+        for name in names {
+            let snapshot_dir = history.dir.join(name);
+            fs::create_dir_all(&snapshot_dir).unwrap();
+            fs::write(snapshot_dir.join(RESULTS_FILE_NAME), "{}").unwrap();
+        }
+
+        (testing_dir, history)
+    }
+
+    #[test]
+    fn test_resolve_snapshot() {
+        let (_testing_dir, history) = history_with(&[
+            "20250101-120000-abc1234",
+            "20250102-090000-def5678",
+            "20250102-180000-0123abc-dirty",
+        ]);
+        let resolved = |query: &str| {
+            history
+                .resolve(query)
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        };
+
+        assert_eq!(
+            resolved("20250101-120000-abc1234").unwrap(),
+            "20250101-120000-abc1234"
+        );
+        assert_eq!(resolved("20250101").unwrap(), "20250101-120000-abc1234");
+        assert_eq!(resolved(LATEST).unwrap(), "20250102-180000-0123abc-dirty");
+        let path = history.dir.join("20250102-090000-def5678");
+        assert_eq!(history.resolve(path.to_str().unwrap()).unwrap(), path);
+
+        let unknown = resolved("20240101").unwrap_err().to_string();
+        assert!(unknown.contains("no snapshot matches"), "{unknown}");
+        let ambiguous = resolved("20250102").unwrap_err().to_string();
+        assert!(ambiguous.contains("ambiguous snapshot"), "{ambiguous}");
+    }
+
+    #[test]
+    fn test_archive_within_same_second() {
+        let (_testing_dir, history) = history_with(&[]);
+        let solution = Solution::new("greedy");
+        // Fast enough that some of them share the timestamp.
+        let names = (0..3)
+            .map(|_| history.archive(&solution, &HashMap::new()).unwrap())
+            .collect_vec();
+
+        assert!(names.iter().all_unique(), "{names:?}");
+        for name in &names {
+            assert_eq!(history.load(name).unwrap().name, *name);
+        }
+        assert_eq!(history.load(LATEST).unwrap().name, names[2]);
+    }
+
+    #[test]
+    fn test_resolve_latest_without_history() {
+        let (_testing_dir, history) = history_with(&[]);
+        assert!(history.resolve(LATEST).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use io::source::Source;
+use library::util::metric;
+use miette::{ensure, Result};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe},
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Once,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::process::{ProcessOutput, ResourceUsage};
+
+/// Exit code of a Rust process terminated by a panic.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Whether a solution has been abandoned on timeout. Its thread keeps taking CPU time from the
+/// cases run after it, so no more cases are started.
+static ABANDONED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Whether this thread is running a solution, whose panics are reported as its failure.
+    static RUNNING_SOLUTION: Cell<bool> = const { Cell::new(false) };
+    static PANIC_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs the solution in a new thread, as if the binary were run with `input` as stdin.
+///
+/// A panic is reported like a process exiting with code 101, with the panic message as stderr.
+/// Other output to stderr is not captured, except for the metrics.
+/// A thread cannot be killed, so a solution which does not finish within `timeout` is abandoned and
+/// keeps running in the background until xtask exits. Cases already running at that time may be
+/// slowed down by it, and later calls fail instead of running the solution.
+/// The CPU time is that of the thread, and memory usage is not measured.
+pub fn run_in_process(solution: &str, input: &[u8], timeout: Duration) -> Result<ProcessOutput> {
+    ensure!(
+        !ABANDONED.load(Ordering::Relaxed),
+        "stopped since a solution which timed out is still running and would slow down the other \
+         cases; run them without --in-process"
+    );
+    install_panic_hook();
+
+    let (sender, receiver) = mpsc::channel();
+    let solution = solution.to_string();
+    let input = input.to_vec();
+    thread::spawn(move || {
+        // The receiver is gone if the solution has been abandoned.
+        let _ = sender.send(run_in_current_thread(&solution, &input, timeout));
+    });
+
+    let output = match receiver.recv_timeout(timeout) {
+        Ok(output) => output,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            ABANDONED.store(true, Ordering::Relaxed);
+            ProcessOutput {
+                // The status of a timed out process is never looked at.
+                status: ExitStatus::default(),
+                stdout: vec![],
+                stderr: vec![],
+                timed_out: true,
+                usage: None,
+            }
+        }
+        // Panics of the solution are caught, so this is a panic while collecting its output.
+        Err(mpsc::RecvTimeoutError::Disconnected) => ProcessOutput {
+            status: exit_status(PANIC_EXIT_CODE),
+            stdout: vec![],
+            stderr: b"solution thread died without output".to_vec(),
+            timed_out: false,
+            usage: None,
+        },
+    };
+
+    Ok(output)
+}
+
+fn run_in_current_thread(solution: &str, input: &[u8], timeout: Duration) -> ProcessOutput {
+    let start_time = Instant::now();
+    let start_cpu_time = thread_cpu_time();
+
+    let mut stdout = vec![];
+    RUNNING_SOLUTION.with(|running| running.set(true));
+    let (result, metric_lines) = metric::capture(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            solutions::run(solution, &mut Source::new(input), &mut stdout)
+        }))
+    });
+    RUNNING_SOLUTION.with(|running| running.set(false));
+
+    let timed_out = start_time.elapsed() > timeout;
+    let cpu_time = thread_cpu_time()
+        .zip(start_cpu_time)
+        .map(|(end, start)| end - start);
+
+    // Metrics are written to stderr as the binary does, so that they are parsed in the same way.
+    let mut stderr = metric_lines.join("\n").into_bytes();
+    if !stderr.is_empty() {
+        stderr.push(b'\n');
+    }
+    let status = match result {
+        Ok(()) => ExitStatus::default(),
+        Err(payload) => {
+            let message = PANIC_MESSAGE
+                .with(|message| message.borrow_mut().take())
+                .unwrap_or_else(|| payload_message(&*payload));
+            stderr.extend(message.into_bytes());
+            exit_status(PANIC_EXIT_CODE)
+        }
+    };
+
+    ProcessOutput {
+        status,
+        stdout,
+        stderr,
+        timed_out,
+        usage: cpu_time.map(|cpu_time| ResourceUsage {
+            cpu_time,
+            peak_rss_kib: None,
+        }),
+    }
+}
+
+/// Captures panics of solutions instead of printing them, keeping the default hook for the others.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if RUNNING_SOLUTION.with(Cell::get) {
+                PANIC_MESSAGE.with(|message| *message.borrow_mut() = Some(info.to_string()));
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+
+    // The raw value is the wait status, which has the exit code in the second byte.
+    ExitStatus::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+
+    ExitStatus::from_raw(code as u32)
+}
+
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    use super::process::Zeroed;
+
+    let mut timespec = libc::timespec::zeroed();
+    // SAFETY: `timespec` is valid for writes.
+    let ret = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut timespec) };
+    if ret != 0 {
+        return None;
+    }
+
+    Some(Duration::new(
+        timespec.tv_sec as u64,
+        timespec.tv_nsec as u32,
+    ))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
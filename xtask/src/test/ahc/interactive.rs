@@ -0,0 +1,237 @@
+use io::traits::WriteInput;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use referee::{
+    validation::{ParsedOutput, Tokens, Validate, ValidationError},
+    InitInput, InteractiveJudge, Output, TurnInput,
+};
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write},
+    process::{ChildStdin, Command},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::process::{read_to_end_in_background, spawn_piped, wait_until, ProcessOutput};
+
+#[derive(Debug)]
+pub struct InteractiveOutput {
+    /// `stdout` contains all outputs of the solution.
+    pub process: ProcessOutput,
+    /// Inputs and outputs of all turns, with the time taken by each turn.
+    pub transcript: String,
+    pub turn_millis: Vec<i64>,
+    /// Score given by the judge, or why the interaction failed.
+    pub score: Result<i64, String>,
+    /// Whether the solution was killed because the judge rejected its output. Its exit status is
+    /// then of no interest.
+    pub killed_on_rejection: bool,
+}
+
+/// How the interaction with the solution ended.
+enum Interaction {
+    Finished,
+    Rejected(String),
+    TimedOut,
+    /// The solution closed its stdout before the interaction finished, most likely by exiting.
+    Exited,
+}
+
+/// Runs `command` interactively against `judge`, and kills it if the whole interaction does not
+/// finish within `timeout`.
+pub fn run_interactive(
+    command: &mut Command,
+    init_input: &InitInput,
+    mut judge: Box<dyn InteractiveJudge>,
+    timeout: Duration,
+) -> Result<InteractiveOutput> {
+    let deadline = Instant::now() + timeout;
+
+    let mut process = spawn_piped(command)?;
+    let child = &mut process.child;
+    let stdin = child.stdin.take().expect("stdin is not piped");
+    let stdout_lines = read_lines_in_background(child.stdout.take().expect("stdout is not piped"));
+    let stderr_reader =
+        read_to_end_in_background(child.stderr.take().expect("stderr is not piped"));
+
+    let mut session = Session {
+        stdin: Some(stdin),
+        stdout_lines,
+        deadline,
+        stdout: vec![],
+        transcript: String::new(),
+        turn_millis: vec![],
+    };
+    let interaction = session.interact(init_input, &mut *judge);
+
+    // Close stdin so that the solution does not wait for further turns. If the interaction did not
+    // finish normally, the solution is of no use any longer.
+    session.stdin = None;
+    let deadline = match interaction {
+        Interaction::Finished | Interaction::Exited => deadline,
+        Interaction::Rejected(_) | Interaction::TimedOut => Instant::now(),
+    };
+    let (status, usage, killed) = wait_until(&mut process, deadline)?;
+
+    let stderr = stderr_reader
+        .join()
+        .expect("stderr reader panicked")
+        .into_diagnostic()
+        .wrap_err("failed to read stderr")?;
+
+    let timed_out = matches!(interaction, Interaction::TimedOut)
+        || (killed && matches!(interaction, Interaction::Finished | Interaction::Exited));
+    let killed_on_rejection = killed && matches!(interaction, Interaction::Rejected(_));
+    let score = match interaction {
+        Interaction::Finished => judge.score().map_err(|e| e.to_string()),
+        Interaction::Rejected(message) => Err(message),
+        Interaction::TimedOut => Err("timed out".to_string()),
+        Interaction::Exited => Err("exited before the interaction finished".to_string()),
+    };
+
+    Ok(InteractiveOutput {
+        process: ProcessOutput {
+            status,
+            stdout: session.stdout,
+            stderr,
+            timed_out,
+            usage,
+        },
+        transcript: session.transcript,
+        turn_millis: session.turn_millis,
+        score,
+        killed_on_rejection,
+    })
+}
+
+/// Reads lines in a separate thread so that reading can time out. The channel is closed at EOF.
+fn read_lines_in_background(reader: impl Read + Send + 'static) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    receiver
+}
+
+struct Session {
+    /// `None` once the solution has closed its stdin.
+    stdin: Option<ChildStdin>,
+    stdout_lines: Receiver<String>,
+    deadline: Instant,
+    stdout: Vec<u8>,
+    transcript: String,
+    turn_millis: Vec<i64>,
+}
+
+impl Session {
+    fn interact(
+        &mut self,
+        init_input: &InitInput,
+        judge: &mut dyn InteractiveJudge,
+    ) -> Interaction {
+        let mut buf = vec![];
+        init_input.write_to(&mut buf);
+        self.send(&buf);
+
+        while let Some(turn_input) = judge.next_turn() {
+            let turn = self.turn_millis.len() + 1;
+            let start_time = Instant::now();
+
+            let mut buf = vec![];
+            turn_input.write_to(&mut buf);
+            self.send(&buf);
+
+            let parsed = match self.receive_output() {
+                Ok(parsed) => parsed,
+                Err(Interaction::Rejected(message)) => {
+                    return Interaction::Rejected(format!("turn {turn}: {message}"))
+                }
+                Err(interaction) => return interaction,
+            };
+            if let Err(e) = validate(init_input, &turn_input, &parsed) {
+                return Interaction::Rejected(format!("turn {turn}: {e}"));
+            }
+
+            let millis = start_time.elapsed().as_millis() as i64;
+            self.turn_millis.push(millis);
+            let _ = writeln!(self.transcript, "# turn {turn}: {millis} ms");
+
+            if let Err(e) = judge.receive(&parsed.output) {
+                let _ = writeln!(self.transcript, "# rejected: {e}");
+                return Interaction::Rejected(format!("turn {turn}: {e}"));
+            }
+        }
+
+        Interaction::Finished
+    }
+
+    fn send(&mut self, input: &[u8]) {
+        for line in String::from_utf8_lossy(input).lines() {
+            let _ = writeln!(self.transcript, "> {line}");
+        }
+
+        // The solution may have exited already. It is reported when its output is read.
+        if let Some(stdin) = &mut self.stdin {
+            if stdin.write_all(input).and_then(|_| stdin.flush()).is_err() {
+                self.stdin = None;
+            }
+        }
+    }
+
+    /// Reads lines until they form a whole `Output`. Otherwise returns how the interaction ended.
+    fn receive_output(&mut self) -> Result<ParsedOutput, Interaction> {
+        let mut text = String::new();
+        loop {
+            let timeout = self.deadline.saturating_duration_since(Instant::now());
+            match self.stdout_lines.recv_timeout(timeout) {
+                Ok(line) => {
+                    let _ = writeln!(self.transcript, "< {}", line.trim_end());
+                    self.stdout.extend(line.as_bytes());
+                    text += &line;
+                }
+                Err(RecvTimeoutError::Timeout) => return Err(Interaction::TimedOut),
+                // The output would have been complete at the last line if it were valid.
+                Err(RecvTimeoutError::Disconnected) => return Err(Interaction::Exited),
+            }
+
+            let mut tokens = Tokens::new(&text);
+            let result =
+                Output::parse_from(&mut tokens).and_then(|(output, operation_positions)| {
+                    tokens.expect_end()?;
+                    Ok(ParsedOutput {
+                        output,
+                        operation_positions,
+                    })
+                });
+            match result {
+                Ok(parsed) => return Ok(parsed),
+                // The output continues on the next line.
+                Err(e) if e.position == Some(tokens.end_position()) => continue,
+                Err(e) => return Err(Interaction::Rejected(e.to_string())),
+            }
+        }
+    }
+}
+
+/// Validates the output of a turn if `Validate` is implemented. Positions are relative to the output
+/// of the turn.
+fn validate(
+    init_input: &InitInput,
+    turn_input: &TurnInput,
+    parsed: &ParsedOutput,
+) -> Result<(), ValidationError> {
+    init_input.validate(turn_input, parsed).unwrap_or(Ok(()))
+}
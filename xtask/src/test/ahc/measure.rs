@@ -0,0 +1,110 @@
+use miette::{bail, IntoDiagnostic, Result, WrapErr};
+use std::{
+    env, fs,
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    process::{Command, ExitStatus},
+    time::Duration,
+};
+use tempfile::TempPath;
+
+use super::process::{wait, ResourceUsage};
+
+/// Hidden task of xtask which runs a command and reports its resource usage.
+pub const TASK: &str = "measure-usage";
+
+// Linux carries the peak RSS of a process over `exec`, and a child spawned by `vfork` starts from
+// the peak of its parent. Spawning commands directly from xtask, which may hold hundreds of MiB of
+// results, would therefore put a floor under the peak memory of every command. Commands are spawned
+// by a fresh xtask process running this task instead, whose footprint is only a few MiB.
+
+/// Wraps `command` to be run by [`main`], which reports the resource usage to the returned file.
+pub fn wrap(command: &Command) -> Result<(Command, TempPath)> {
+    let usage_path = tempfile::NamedTempFile::new()
+        .into_diagnostic()
+        .wrap_err("failed to create usage file")?
+        .into_temp_path();
+    let xtask = env::current_exe()
+        .into_diagnostic()
+        .wrap_err("failed to locate xtask executable")?;
+
+    let mut wrapped = Command::new(xtask);
+    wrapped
+        .arg(TASK)
+        .arg(&usage_path)
+        .arg(command.get_program())
+        .args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => wrapped.env(key, value),
+            None => wrapped.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        wrapped.current_dir(dir);
+    }
+
+    Ok((wrapped, usage_path))
+}
+
+/// Reads the usage reported by [`main`]. `None` if it was killed before reporting.
+pub fn read_usage(usage_path: &Path) -> Option<ResourceUsage> {
+    let content = fs::read_to_string(usage_path).ok()?;
+    let (cpu_micros, peak_rss_kib) = content.trim().split_once(' ')?;
+
+    Some(ResourceUsage {
+        cpu_time: Duration::from_micros(cpu_micros.parse().ok()?),
+        peak_rss_kib: Some(peak_rss_kib.parse().ok()?),
+    })
+}
+
+/// Runs `<program> [args...]`, writes its resource usage to `<usage file>` and exits in the same way
+/// as the program.
+///
+/// This runs in place of the program, so nothing else is written to stdout or stderr unless it fails.
+pub fn main(args: &[String]) -> Result<()> {
+    let [usage_path, program, args @ ..] = args else {
+        bail!("usage: xtask {TASK} <usage file> <program> [args...]");
+    };
+
+    // The runner sends SIGTERM to the whole process group at the deadline. Survive it to report the
+    // usage of the terminated program. Unlike ignored signals, handlers are reset by `exec`, so the
+    // program still terminates on SIGTERM.
+    extern "C" fn survive(_: libc::c_int) {}
+    // SAFETY: `survive` does nothing, which is async-signal-safe.
+    unsafe { libc::signal(libc::SIGTERM, survive as *const () as libc::sighandler_t) };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .spawn()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to spawn {program}"))?;
+    let (status, usage) = wait(&mut child, true)
+        .wrap_err("failed to wait for process")?
+        .expect("blocking wait returned no status");
+    let usage = usage.expect("resource usage is always measured on Linux");
+    fs::write(
+        usage_path,
+        format!(
+            "{} {}",
+            usage.cpu_time.as_micros(),
+            usage.peak_rss_kib.unwrap_or_default()
+        ),
+    )
+    .into_diagnostic()
+    .wrap_err("failed to write usage file")?;
+
+    exit_like(status)
+}
+
+fn exit_like(status: ExitStatus) -> ! {
+    if let Some(signal) = status.signal() {
+        // SAFETY: `signal` and `raise` have no memory safety requirements.
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+
+    std::process::exit(status.code().unwrap_or(1))
+}
@@ -0,0 +1,227 @@
+use miette::{bail, ensure, IntoDiagnostic, Result, WrapErr};
+use std::{ops::RangeInclusive, path::PathBuf, time::Duration};
+
+/// Command line options for `cargo xtask test`.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Indices of the seeds in `seeds.txt` to test. `None` means all seeds.
+    pub seeds: Option<SeedSelection>,
+    /// Names of the solutions to test. Empty means all solutions.
+    pub solutions: Vec<String>,
+    /// Number of parallel jobs. `None` lets rayon decide.
+    pub jobs: Option<usize>,
+    /// Ignore cached results and rerun every selected solution.
+    pub force: bool,
+    /// Do not rerun cached results of non-primary solutions whose source has changed.
+    pub keep_stale: bool,
+    /// Wall-clock limit for each case. `None` uses the default derived from `TIME_LIMIT`.
+    pub timeout: Option<Duration>,
+    /// Parameter (one of `InitInput::description_keys`) to aggregate relative scores by.
+    pub group_by: Option<String>,
+    /// Number of buckets for a numeric `group_by` parameter. `None` uses the default.
+    pub buckets: Option<usize>,
+    /// File to export the results to, as CSV or JSON Lines depending on the extension.
+    pub export: Option<PathBuf>,
+    /// Show the CPU time of each case in the table.
+    pub show_cpu: bool,
+    /// Show the peak memory usage of each case in the table.
+    pub show_memory: bool,
+    /// Show the values reported by `library::metric!` in the table.
+    pub show_metrics: bool,
+    /// Snapshot in the history to compare the primary solution with.
+    pub compare: Option<String>,
+    /// Call the solutions in this process instead of spawning the binary for each case.
+    pub in_process: bool,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value_of = |name: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| miette::miette!("missing value for {}", name))
+            };
+
+            match &**arg {
+                "--seeds" => {
+                    let value = value_of(arg)?;
+                    options.seeds = Some(
+                        SeedSelection::parse(&value)
+                            .wrap_err_with(|| format!("invalid seed selection: {value}"))?,
+                    );
+                }
+                "--solution" => options.solutions.push(value_of(arg)?),
+                "--jobs" => {
+                    let value = value_of(arg)?;
+                    let jobs = value
+                        .parse()
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("invalid number of jobs: {value}"))?;
+                    ensure!(jobs > 0, "number of jobs must be positive");
+                    options.jobs = Some(jobs);
+                }
+                "--force" => options.force = true,
+                "--keep-stale" => options.keep_stale = true,
+                "--show-cpu" => options.show_cpu = true,
+                "--show-memory" => options.show_memory = true,
+                "--show-metrics" => options.show_metrics = true,
+                "--in-process" => options.in_process = true,
+                "--timeout" => {
+                    let value = value_of(arg)?;
+                    let millis = value
+                        .parse()
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("invalid timeout in milliseconds: {value}"))?;
+                    options.timeout = Some(Duration::from_millis(millis));
+                }
+                "--group-by" => options.group_by = Some(value_of(arg)?),
+                "--buckets" => {
+                    let value = value_of(arg)?;
+                    let buckets = value
+                        .parse()
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("invalid number of buckets: {value}"))?;
+                    ensure!(buckets > 0, "number of buckets must be positive");
+                    options.buckets = Some(buckets);
+                }
+                "--export" => options.export = Some(PathBuf::from(value_of(arg)?)),
+                "--compare" => options.compare = Some(value_of(arg)?),
+                _ => bail!("unknown option: {}", arg),
+            }
+        }
+
+        Ok(options)
+    }
+
+    pub fn is_seed_selected(&self, index: usize) -> bool {
+        self.seeds
+            .as_ref()
+            .is_none_or(|selection| selection.contains(index))
+    }
+}
+
+/// Comma-separated list of seed indices or ranges, such as `0..50,100,200..=210`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedSelection {
+    ranges: Vec<RangeInclusive<usize>>,
+}
+
+impl SeedSelection {
+    pub fn parse(s: &str) -> Result<Self> {
+        let ranges = s
+            .split(',')
+            .map(|part| Self::parse_range(part.trim()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { ranges })
+    }
+
+    fn parse_range(s: &str) -> Result<RangeInclusive<usize>> {
+        let parse_index = |s: &str| -> Result<usize> {
+            s.parse()
+                .into_diagnostic()
+                .wrap_err_with(|| format!("invalid seed index: {s}"))
+        };
+
+        if let Some((start, end)) = s.split_once("..=") {
+            let (start, end) = (parse_index(start)?, parse_index(end)?);
+            ensure!(start <= end, "empty seed range: {}", s);
+            Ok(start..=end)
+        } else if let Some((start, end)) = s.split_once("..") {
+            let (start, end) = (parse_index(start)?, parse_index(end)?);
+            ensure!(start < end, "empty seed range: {}", s);
+            Ok(start..=end - 1)
+        } else {
+            let index = parse_index(s)?;
+            Ok(index..=index)
+        }
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = Options::parse(&args(&[
+            "--seeds",
+            "0..3,10",
+            "--solution",
+            "naive",
+            "--solution",
+            "greedy",
+            "--jobs",
+            "4",
+            "--force",
+            "--show-cpu",
+            "--show-metrics",
+            "--timeout",
+            "1500",
+            "--group-by",
+            "N",
+            "--buckets",
+            "3",
+            "--export",
+            "results.csv",
+            "--compare",
+            "latest",
+            "--in-process",
+        ]))
+        .unwrap();
+
+        assert_eq!(options.solutions, vec!["naive", "greedy"]);
+        assert_eq!(options.jobs, Some(4));
+        assert!(options.force);
+        assert!(options.show_cpu);
+        assert!(!options.show_memory);
+        assert!(options.show_metrics);
+        assert_eq!(options.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(options.group_by.as_deref(), Some("N"));
+        assert_eq!(options.buckets, Some(3));
+        assert_eq!(options.export, Some(PathBuf::from("results.csv")));
+        assert_eq!(options.compare.as_deref(), Some("latest"));
+        assert!(options.in_process);
+        assert!(options.is_seed_selected(0));
+        assert!(options.is_seed_selected(2));
+        assert!(!options.is_seed_selected(3));
+        assert!(options.is_seed_selected(10));
+        assert!(!options.is_seed_selected(11));
+    }
+
+    #[test]
+    fn test_parse_seed_selection() {
+        let selection = SeedSelection::parse("5..=7").unwrap();
+        assert!(!selection.contains(4));
+        assert!(selection.contains(5));
+        assert!(selection.contains(7));
+        assert!(!selection.contains(8));
+
+        assert!(SeedSelection::parse("3..0").is_err());
+        assert!(SeedSelection::parse("5..3").is_err());
+        assert!(SeedSelection::parse("5..=3").is_err());
+        assert!(SeedSelection::parse("3..3").is_err());
+        assert!(SeedSelection::parse("3..=3").unwrap().contains(3));
+        assert!(SeedSelection::parse("a..b").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_options() {
+        assert!(Options::parse(&args(&["--jobs"])).is_err());
+        assert!(Options::parse(&args(&["--jobs", "0"])).is_err());
+        assert!(Options::parse(&args(&["--buckets", "0"])).is_err());
+        assert!(Options::parse(&args(&["--unknown"])).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    io::{Read, Write},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(target_os = "linux")]
+use super::measure;
+
+/// Interval to poll the process status while waiting for it to finish.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Time given to a process to exit after SIGTERM before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub timed_out: bool,
+    /// Only available on Linux.
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Resources used by the process and the descendants it waited for.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceUsage {
+    /// User and system CPU time.
+    pub cpu_time: Duration,
+    /// Linux carries the peak over `exec`, so this is at least the memory of the process which
+    /// spawned it. Commands are spawned through `measure` to keep it a few MiB. `None` if not
+    /// measured.
+    pub peak_rss_kib: Option<u64>,
+}
+
+/// A process spawned by `spawn_piped`.
+#[derive(Debug)]
+pub struct Process {
+    pub child: Child,
+    /// File to which `measure` reports the resource usage of the command.
+    #[cfg(target_os = "linux")]
+    usage_path: tempfile::TempPath,
+}
+
+impl Process {
+    /// Resource usage of the command, if it has been reported.
+    fn usage(&self) -> Option<ResourceUsage> {
+        #[cfg(target_os = "linux")]
+        return measure::read_usage(&self.usage_path);
+        #[cfg(not(target_os = "linux"))]
+        return None;
+    }
+}
+
+/// Runs `command` feeding `stdin`, and kills it (together with its children) if it does not finish
+/// within `timeout`.
+pub fn run_with_timeout(
+    command: &mut Command,
+    stdin: Vec<u8>,
+    timeout: Duration,
+) -> Result<ProcessOutput> {
+    let deadline = Instant::now() + timeout;
+    let mut process = spawn_piped(command)?;
+    let child = &mut process.child;
+
+    // Feed stdin and drain stdout/stderr in separate threads; otherwise the process may block
+    // forever on a full pipe.
+    let mut child_stdin = child.stdin.take().expect("stdin is not piped");
+    let stdin_writer = thread::spawn(move || {
+        // The process may exit without reading all of its input. It is not our error.
+        let _ = child_stdin.write_all(&stdin);
+    });
+    let stdout_reader =
+        read_to_end_in_background(child.stdout.take().expect("stdout is not piped"));
+    let stderr_reader =
+        read_to_end_in_background(child.stderr.take().expect("stderr is not piped"));
+
+    let (status, usage, timed_out) = wait_until(&mut process, deadline)?;
+
+    stdin_writer.join().expect("stdin writer panicked");
+    let stdout = stdout_reader
+        .join()
+        .expect("stdout reader panicked")
+        .into_diagnostic()
+        .wrap_err("failed to read stdout")?;
+    let stderr = stderr_reader
+        .join()
+        .expect("stderr reader panicked")
+        .into_diagnostic()
+        .wrap_err("failed to read stderr")?;
+
+    Ok(ProcessOutput {
+        status,
+        stdout,
+        stderr,
+        timed_out,
+        usage,
+    })
+}
+
+/// Spawns `command` with all of stdin, stdout and stderr piped.
+pub fn spawn_piped(command: &mut Command) -> Result<Process> {
+    #[cfg(target_os = "linux")]
+    let (mut command, usage_path) = measure::wrap(command)?;
+    #[cfg(target_os = "linux")]
+    let command = &mut command;
+
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Put the process into its own process group so that we can kill the solution spawned by the
+    // interactive tester as well. The group no longer receives Ctrl-C from the terminal, so it is
+    // registered to be killed when we are interrupted.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+
+    let child = command
+        .spawn()
+        .into_diagnostic()
+        .wrap_err("failed to spawn process")?;
+    #[cfg(unix)]
+    process_groups::register(child.id() as libc::pid_t);
+
+    Ok(Process {
+        child,
+        #[cfg(target_os = "linux")]
+        usage_path,
+    })
+}
+
+pub fn read_to_end_in_background(
+    mut reader: impl Read + Send + 'static,
+) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).map(|_| buf)
+    })
+}
+
+/// Waits for the process to exit, killing it at `deadline`. Returns whether it was killed as well.
+pub fn wait_until(
+    process: &mut Process,
+    deadline: Instant,
+) -> Result<(ExitStatus, Option<ResourceUsage>, bool)> {
+    let child = &mut process.child;
+    if let Some(status) = wait_for(child, deadline).wrap_err("failed to wait for process")? {
+        return Ok((status, process.usage(), false));
+    }
+
+    // Terminate the process first so that `measure` can report the usage of the command.
+    terminate(child).wrap_err("failed to terminate timed out process")?;
+    let grace_deadline = Instant::now() + KILL_GRACE;
+    let status =
+        match wait_for(child, grace_deadline).wrap_err("failed to wait for terminated process")? {
+            Some(status) => status,
+            None => {
+                kill(child).wrap_err("failed to kill timed out process")?;
+                let (status, _) = wait(child, true)
+                    .wrap_err("failed to wait for killed process")?
+                    .expect("blocking wait returned no status");
+                status
+            }
+        };
+
+    Ok((status, process.usage(), true))
+}
+
+/// Waits for the process to exit until `deadline`. `None` if it is still running.
+fn wait_for(child: &mut Child, deadline: Instant) -> Result<Option<ExitStatus>> {
+    loop {
+        if let Some((status, _)) = wait(child, false)? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Reaps the process if it has exited, or blocks until it exits if `block` is set. On Linux the
+/// resource usage is collected as well.
+#[cfg(target_os = "linux")]
+pub(super) fn wait(
+    child: &mut Child,
+    block: bool,
+) -> Result<Option<(ExitStatus, Option<ResourceUsage>)>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let flags = if block { 0 } else { libc::WNOHANG };
+    let mut status = 0;
+    let mut rusage = libc::rusage::zeroed();
+    loop {
+        // SAFETY: `status` and `rusage` are valid for writes. The pid is our own child which has
+        // not been reaped yet; `Child` is never waited for after this reaps it.
+        let ret =
+            unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, flags, &mut rusage) };
+        match ret {
+            0 => return Ok(None),
+            -1 => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(error).into_diagnostic().wrap_err("wait4 failed");
+            }
+            _ => break,
+        }
+    }
+    process_groups::unregister(child.id() as libc::pid_t);
+
+    let timeval = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+    let usage = ResourceUsage {
+        cpu_time: timeval(rusage.ru_utime) + timeval(rusage.ru_stime),
+        // `ru_maxrss` is in kilobytes on Linux.
+        peak_rss_kib: Some(rusage.ru_maxrss as u64),
+    };
+
+    Ok(Some((ExitStatus::from_raw(status), Some(usage))))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn wait(child: &mut Child, block: bool) -> Result<Option<(ExitStatus, Option<ResourceUsage>)>> {
+    let status = if block {
+        Some(child.wait().into_diagnostic()?)
+    } else {
+        child.try_wait().into_diagnostic()?
+    };
+    #[cfg(unix)]
+    if status.is_some() {
+        process_groups::unregister(child.id() as libc::pid_t);
+    }
+
+    Ok(status.map(|status| (status, None)))
+}
+
+/// C structs to be filled in by libc.
+#[cfg(unix)]
+pub(super) trait Zeroed: Sized {
+    fn zeroed() -> Self {
+        // SAFETY: only implemented for plain C structs of integers, for which all-zero bytes are a
+        // valid value.
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+#[cfg(unix)]
+impl Zeroed for libc::rusage {}
+
+#[cfg(unix)]
+impl Zeroed for libc::timespec {}
+
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> Result<()> {
+    signal_group(child, libc::SIGTERM)
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) -> Result<()> {
+    signal_group(child, libc::SIGKILL)
+}
+
+#[cfg(unix)]
+fn signal_group(child: &mut Child, signal: libc::c_int) -> Result<()> {
+    // SAFETY: `kill` has no memory safety requirements. The negative pid designates the process
+    // group created in `spawn_piped`.
+    let ret = unsafe { libc::kill(-(child.id() as libc::pid_t), signal) };
+    // The process may have exited just before we kill it.
+    let error = std::io::Error::last_os_error();
+    if ret != 0 && error.raw_os_error() != Some(libc::ESRCH) {
+        return Err(error)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to send signal {signal} to process group"));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) -> Result<()> {
+    kill(child)
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) -> Result<()> {
+    child
+        .kill()
+        .into_diagnostic()
+        .wrap_err("failed to kill process")
+}
+
+/// Process groups of the running processes, which are killed when we receive SIGINT or SIGTERM.
+///
+/// The signal handler may only use async-signal-safe operations, so the groups are kept in a fixed
+/// table of atomics rather than behind a lock.
+#[cfg(unix)]
+mod process_groups {
+    use std::sync::{
+        atomic::{AtomicI32, Ordering},
+        Once,
+    };
+
+    /// Far more than the number of processes running at once. A process that does not fit is simply
+    /// not killed on interruption.
+    const CAPACITY: usize = 1024;
+
+    /// Process group ids, or 0 for a free slot.
+    static GROUPS: [AtomicI32; CAPACITY] = [const { AtomicI32::new(0) }; CAPACITY];
+    static INSTALL_HANDLER: Once = Once::new();
+
+    pub fn register(pgid: libc::pid_t) {
+        INSTALL_HANDLER.call_once(install_handler);
+        let _ = GROUPS.iter().find(|slot| {
+            slot.compare_exchange(0, pgid, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        });
+    }
+
+    pub fn unregister(pgid: libc::pid_t) {
+        let _ = GROUPS.iter().find(|slot| {
+            slot.compare_exchange(pgid, 0, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        });
+    }
+
+    fn install_handler() {
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            // SAFETY: `kill_groups` only calls async-signal-safe functions.
+            unsafe { libc::signal(signal, kill_groups as *const () as libc::sighandler_t) };
+        }
+    }
+
+    extern "C" fn kill_groups(signal: libc::c_int) {
+        for slot in &GROUPS {
+            let pgid = slot.load(Ordering::SeqCst);
+            if pgid != 0 {
+                // SAFETY: `kill` is async-signal-safe.
+                unsafe { libc::kill(-pgid, libc::SIGKILL) };
+            }
+        }
+
+        // Terminate by the signal as if it were not handled.
+        // SAFETY: `signal` and `raise` are async-signal-safe.
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
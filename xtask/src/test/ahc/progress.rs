@@ -0,0 +1,138 @@
+use std::{
+    io::{stderr, IsTerminal, Write},
+    sync::Mutex,
+    time::Duration,
+};
+
+use super::TestCaseResult;
+
+/// Reports the progress of a solution run to stderr. On a terminal a single status line is
+/// redrawn; otherwise one line is logged per finished case.
+#[derive(Debug)]
+pub struct Progress {
+    solution: String,
+    total: usize,
+    parallelism: usize,
+    interactive: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    completed: usize,
+    failed: usize,
+    total_duration_millis: i64,
+    score_sum: u64,
+    score_count: usize,
+    /// Sums over the cases successful in both this run and the previous cached run.
+    paired_score_sum: u64,
+    paired_previous_score_sum: u64,
+}
+
+impl Progress {
+    pub fn new(solution: &str, total: usize) -> Self {
+        Self {
+            solution: solution.to_string(),
+            total,
+            parallelism: rayon::current_num_threads(),
+            interactive: stderr().is_terminal(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Records a finished case. `previous` is the cached result of the same case, if any.
+    pub fn record(&self, result: &TestCaseResult, previous: Option<&TestCaseResult>) {
+        let mut state = self.state.lock().expect("progress state is poisoned");
+        state.completed += 1;
+        state.total_duration_millis += result.duration_millis;
+        match &result.score {
+            Ok(score) => {
+                state.score_sum += score;
+                state.score_count += 1;
+                if let Some(Ok(previous_score)) = previous.map(|previous| &previous.score) {
+                    state.paired_score_sum += score;
+                    state.paired_previous_score_sum += previous_score;
+                }
+            }
+            Err(_) => state.failed += 1,
+        }
+
+        if self.interactive {
+            eprint!("\r\x1b[2K{}", self.status_line(&state));
+            let _ = stderr().flush();
+        } else {
+            eprintln!(
+                "[{}/{}] {} by {}: {} ({} ms)",
+                state.completed,
+                self.total,
+                result.in_filename,
+                self.solution,
+                result.score_display(),
+                result.duration_millis
+            );
+        }
+    }
+
+    /// Ends the status line. Must be called once all cases are recorded.
+    pub fn finish(&self) {
+        let state = self.state.lock().expect("progress state is poisoned");
+        if self.interactive {
+            eprintln!();
+        } else {
+            eprintln!("{}", self.status_line(&state));
+        }
+    }
+
+    fn status_line(&self, state: &State) -> String {
+        let mut line = format!(
+            "{}: {}/{} done, {} failed",
+            self.solution, state.completed, self.total, state.failed
+        );
+
+        if state.score_count > 0 {
+            line += &format!(
+                ", mean {:.1}",
+                state.score_sum as f64 / state.score_count as f64
+            );
+            if state.paired_previous_score_sum > 0 {
+                let change = state.paired_score_sum as f64 / state.paired_previous_score_sum as f64;
+                line += &format!(" ({:+.2}% vs cached)", (change - 1.0) * 100.0);
+            }
+        }
+
+        let remaining = self.total - state.completed;
+        if remaining > 0 {
+            // Cases run in parallel, so the remaining cases take `1 / parallelism` of their total
+            // duration.
+            let mean_millis = state.total_duration_millis as f64 / state.completed as f64;
+            let eta_millis = mean_millis * remaining as f64 / self.parallelism as f64;
+            line += &format!(
+                ", ETA {}",
+                format_duration(Duration::from_millis(eta_millis as u64))
+            );
+        }
+
+        line
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, s) => format!("{h}h{m:02}m{s:02}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(5900)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(63)), "1m03s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+    }
+}
@@ -0,0 +1,129 @@
+use itertools::Itertools;
+use serde::Deserialize;
+
+use super::AbsoluteBetterIs;
+
+/// How the per-seed relative score (and hence the total) is computed from the absolute scores of
+/// the compared solutions.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scoring {
+    /// `value / best` when maximizing, `best / value` when minimizing.
+    #[default]
+    Ratio,
+    /// `round(1e9 * ratio)`, the relative score of most AtCoder Heuristic Contests.
+    Relative,
+    /// Ratio of `ln(1 + score)`, for contests whose scores span several orders of magnitude.
+    Log,
+    /// Fraction of the other solutions beaten on the seed, counting ties as half.
+    Rank,
+    /// Absolute score as is, so that the total is the plain sum.
+    Raw,
+}
+
+impl Scoring {
+    /// Computes relative scores of the solutions on one seed. `None` means the solution failed,
+    /// which always results in zero.
+    pub fn relative_scores(&self, objective: AbsoluteBetterIs, scores: &[Option<u64>]) -> Vec<f64> {
+        let best = objective.is_which(scores.iter().flatten().copied());
+
+        scores
+            .iter()
+            .map(|&score| {
+                let (Some(score), Some(best)) = (score, best) else {
+                    return 0.0;
+                };
+
+                match self {
+                    Self::Ratio => objective.make_relative_goodness(score, best),
+                    Self::Relative => (1e9 * objective.make_relative_goodness(score, best)).round(),
+                    Self::Log => {
+                        let ln = |x: u64| (1.0 + x as f64).ln();
+                        match objective {
+                            AbsoluteBetterIs::Minimum => ln(best) / ln(score).max(f64::EPSILON),
+                            AbsoluteBetterIs::Maximum => ln(score) / ln(best).max(f64::EPSILON),
+                        }
+                    }
+                    Self::Rank => Self::rank_score(objective, score, scores),
+                    Self::Raw => score as f64,
+                }
+            })
+            .collect_vec()
+    }
+
+    fn rank_score(objective: AbsoluteBetterIs, score: u64, scores: &[Option<u64>]) -> f64 {
+        let others = scores.len() - 1;
+        if others == 0 {
+            return 1.0;
+        }
+
+        // `scores` contains `score` itself, which is counted as a tie.
+        let (beaten, tied) = scores
+            .iter()
+            .fold((0, 0), |(beaten, tied), other| match other {
+                None => (beaten + 1, tied),
+                Some(other) if *other == score => (beaten, tied + 1),
+                Some(other) if objective.is_former(score, *other) => (beaten + 1, tied),
+                Some(_) => (beaten, tied),
+            });
+
+        (beaten as f64 + (tied - 1) as f64 / 2.0) / others as f64
+    }
+
+    pub fn format(&self, relative_score: f64) -> String {
+        match self {
+            Self::Ratio | Self::Log | Self::Rank => format!("{:.8}", relative_score),
+            Self::Relative | Self::Raw => format!("{:.0}", relative_score),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ratio_and_relative() {
+        let scores = [Some(50), Some(100), None];
+
+        assert_eq!(
+            Scoring::Ratio.relative_scores(AbsoluteBetterIs::Maximum, &scores),
+            vec![0.5, 1.0, 0.0]
+        );
+        assert_eq!(
+            Scoring::Ratio.relative_scores(AbsoluteBetterIs::Minimum, &scores),
+            vec![1.0, 0.5, 0.0]
+        );
+        assert_eq!(
+            Scoring::Relative.relative_scores(AbsoluteBetterIs::Maximum, &scores),
+            vec![5e8, 1e9, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let scores = [Some(10), Some(30), Some(30), None];
+
+        assert_eq!(
+            Scoring::Rank.relative_scores(AbsoluteBetterIs::Maximum, &scores),
+            vec![1.0 / 3.0, 2.5 / 3.0, 2.5 / 3.0, 0.0]
+        );
+        assert_eq!(
+            Scoring::Rank.relative_scores(AbsoluteBetterIs::Minimum, &[Some(10)]),
+            vec![1.0]
+        );
+    }
+
+    #[test]
+    fn test_log_and_raw() {
+        let scores = [Some(0), Some(99)];
+
+        let log = Scoring::Log.relative_scores(AbsoluteBetterIs::Maximum, &scores);
+        assert_eq!(log[0], 0.0);
+        assert_eq!(log[1], 1.0);
+        assert_eq!(
+            Scoring::Raw.relative_scores(AbsoluteBetterIs::Maximum, &scores),
+            vec![0.0, 99.0]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use itertools::Itertools;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::sources::{find_solution_modules, rust_files_in, DEPENDENCY_DIRS, SOLUTIONS_SRC_DIR};
+
+use super::Solution;
+
+/// Computes the content hash of each solution, covering its module files, the rest of the
+/// `solutions` crate shared by all solutions (`lib.rs` and modules not implementing a solution) and
+/// the crates it depends on, together with the workspace manifest and lock file which pin their
+/// dependencies. `None` means the module of the solution could not be located, so that its cached
+/// results are never considered stale.
+pub fn compute_source_hashes(solutions: &[Solution]) -> Result<HashMap<Solution, Option<String>>> {
+    let modules = find_solution_modules().wrap_err("failed to find solution modules")?;
+
+    // Files of other solutions do not affect the results of this one.
+    let solution_files = modules
+        .values()
+        .flat_map(|module| &module.files)
+        .collect::<HashSet<_>>();
+    let mut dependency_files = vec![
+        PathBuf::from("Cargo.toml"),
+        PathBuf::from("Cargo.lock"),
+        Path::new(SOLUTIONS_SRC_DIR).with_file_name("Cargo.toml"),
+    ];
+    dependency_files.extend(
+        rust_files_in(Path::new(SOLUTIONS_SRC_DIR))?
+            .into_iter()
+            .filter(|file| !solution_files.contains(file)),
+    );
+    for dir in DEPENDENCY_DIRS {
+        let dir = Path::new(dir);
+        dependency_files.push(dir.join("Cargo.toml"));
+        dependency_files.extend(rust_files_in(&dir.join("src"))?);
+    }
+
+    solutions
+        .iter()
+        .map(|solution| {
+            let Some(module) = modules.get(solution.inner()) else {
+                eprintln!(
+                    "warning: module of solution {} not found; staleness is not tracked",
+                    solution.inner()
+                );
+                return Ok((solution.clone(), None));
+            };
+
+            let files = module.files.iter().chain(&dependency_files).collect_vec();
+            Ok((solution.clone(), Some(hash_files(&files)?)))
+        })
+        .collect()
+}
+
+fn hash_files(files: &[&PathBuf]) -> Result<String> {
+    let mut contents = vec![];
+    for file in files {
+        // Include the path so that moving code between files changes the hash.
+        contents.extend(file.to_string_lossy().as_bytes());
+        contents.push(0);
+        contents.extend(
+            fs::read(file)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to read {}", file.display()))?,
+        );
+        contents.push(0);
+    }
+
+    Ok(sha256::digest(contents))
+}
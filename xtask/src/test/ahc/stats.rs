@@ -0,0 +1,125 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Number of resamples to estimate bootstrap confidence intervals.
+const BOOTSTRAP_ITERATIONS: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let sorted = values
+            .iter()
+            .copied()
+            .sorted_by(f64::total_cmp)
+            .collect_vec();
+        let n = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        };
+        // Sample standard deviation; zero for a single value.
+        let stddev = if n > 1 {
+            (sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            mean,
+            median,
+            stddev,
+            min: sorted[0],
+            max: sorted[n - 1],
+        })
+    }
+}
+
+/// Two-sided p-value of the sign test, i.e. the probability that a fair coin gives a split at
+/// least as uneven as `wins` vs `losses`. Ties must be excluded beforehand.
+pub fn sign_test_p_value(wins: usize, losses: usize) -> f64 {
+    let n = wins + losses;
+    let k = wins.min(losses);
+
+    // Sum up binomial probabilities in log space; `0.5^n` underflows for large `n`.
+    let mut ln_term = -(n as f64) * 2f64.ln();
+    let mut tail = 0.0;
+    for i in 0..=k {
+        tail += ln_term.exp();
+        ln_term += ((n - i) as f64).ln() - ((i + 1) as f64).ln();
+    }
+
+    (2.0 * tail).min(1.0)
+}
+
+/// 95% bootstrap confidence interval of the mean of `values`.
+pub fn bootstrap_mean_ci(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+
+    // Fixed seed to keep the summary stable between runs of the same results.
+    let mut rng = StdRng::seed_from_u64(0);
+    let means = (0..BOOTSTRAP_ITERATIONS)
+        .map(|_| {
+            let sum: f64 = (0..values.len())
+                .map(|_| values[rng.gen_range(0..values.len())])
+                .sum();
+            sum / values.len() as f64
+        })
+        .sorted_by(f64::total_cmp)
+        .collect_vec();
+
+    let percentile = |p: f64| means[((means.len() - 1) as f64 * p).round() as usize];
+    Some((percentile(0.025), percentile(0.975)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::of(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(summary.mean, 2.5);
+        assert_eq!(summary.median, 2.5);
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.max, 4.0);
+        assert!((summary.stddev - (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+
+        assert_eq!(Summary::of(&[]), None);
+        assert_eq!(Summary::of(&[7.0]).unwrap().stddev, 0.0);
+    }
+
+    #[test]
+    fn test_sign_test() {
+        assert_eq!(sign_test_p_value(0, 0), 1.0);
+        assert_eq!(sign_test_p_value(5, 5), 1.0);
+        // P(X <= 1) for Bin(10, 0.5) is 11 / 1024.
+        assert!((sign_test_p_value(9, 1) - 22.0 / 1024.0).abs() < 1e-12);
+        assert!(sign_test_p_value(2000, 0) < 1e-300);
+    }
+
+    #[test]
+    fn test_bootstrap_mean_ci() {
+        let (low, high) = bootstrap_mean_ci(&[1.0; 10]).unwrap();
+        assert_eq!((low, high), (1.0, 1.0));
+
+        let values = (0..100).map(|x| x as f64).collect_vec();
+        let (low, high) = bootstrap_mean_ci(&values).unwrap();
+        assert!(low < 49.5 && 49.5 < high);
+    }
+}
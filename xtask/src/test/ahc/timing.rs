@@ -0,0 +1,81 @@
+use referee::TIME_LIMIT;
+use serde::Deserialize;
+
+/// Time limit of the judge, read from the `[judge]` table of `xtask.toml`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JudgeLimits {
+    pub time_limit_millis: i64,
+    /// Cases finishing within this margin below the limit are flagged as risky.
+    pub margin_millis: i64,
+}
+
+impl Default for JudgeLimits {
+    fn default() -> Self {
+        // Solutions stop by themselves at `TIME_LIMIT`, which is a bit below the actual limit.
+        // Assume the limit is the next whole second.
+        let time_limit_millis = (TIME_LIMIT.as_millis() as i64 + 999) / 1000 * 1000;
+
+        Self {
+            time_limit_millis,
+            margin_millis: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TimingStatus {
+    Safe,
+    /// Within the margin below the limit.
+    Risky,
+    /// Over the limit.
+    Exceeded,
+}
+
+impl JudgeLimits {
+    /// Classifies a case by the larger of its wall-clock and CPU time.
+    pub fn classify(&self, duration_millis: i64, cpu_time_millis: Option<i64>) -> TimingStatus {
+        let millis = duration_millis.max(cpu_time_millis.unwrap_or(0));
+        if millis > self.time_limit_millis {
+            TimingStatus::Exceeded
+        } else if millis > self.time_limit_millis - self.margin_millis {
+            TimingStatus::Risky
+        } else {
+            TimingStatus::Safe
+        }
+    }
+}
+
+impl TimingStatus {
+    /// Marker prepended to the score in the table.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Safe => "",
+            Self::Risky => "~",
+            Self::Exceeded => "!",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_time_limit() {
+        assert_eq!(JudgeLimits::default().time_limit_millis, 6000);
+    }
+
+    #[test]
+    fn test_classify() {
+        let limits = JudgeLimits {
+            time_limit_millis: 2000,
+            margin_millis: 100,
+        };
+
+        assert_eq!(limits.classify(1900, None), TimingStatus::Safe);
+        assert_eq!(limits.classify(1901, Some(10)), TimingStatus::Risky);
+        assert_eq!(limits.classify(1500, Some(2001)), TimingStatus::Exceeded);
+        assert_eq!(limits.classify(2001, None), TimingStatus::Exceeded);
+    }
+}
@@ -0,0 +1,37 @@
+use miette::{bail, IntoDiagnostic, Result, WrapErr};
+use std::{io::Read, path::Path};
+
+use crate::bundle::source_map::{SourceMap, MAP_PATH};
+
+/// Translates lines of the bundled submission back to the original sources, using the source map
+/// written by `xtask bundle --annotate`.
+///
+/// With line numbers as arguments, prints the original location of each line. Without arguments,
+/// reads a panic message or backtrace from stdin and rewrites the `main.rs` locations in it.
+pub fn main(args: &[String]) -> Result<()> {
+    let source_map = SourceMap::read(Path::new(MAP_PATH))
+        .wrap_err("run `cargo xtask bundle --annotate` to write the source map")?;
+
+    if args.is_empty() {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .into_diagnostic()
+            .wrap_err("failed to read stdin")?;
+        print!("{}", source_map.unmap_text(&text));
+
+        return Ok(());
+    }
+
+    for arg in args {
+        let Ok(line) = arg.parse::<usize>() else {
+            bail!("invalid line number: {arg}");
+        };
+        match source_map.lookup(line) {
+            Some(location) => println!("{line}: {location}"),
+            None => println!("{line}: unknown"),
+        }
+    }
+
+    Ok(())
+}